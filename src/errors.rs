@@ -1,16 +1,59 @@
 use std::fmt::Write;
 use std::panic::Location;
+use std::sync::OnceLock;
 
+use pyo3::create_exception;
 use pyo3::exceptions::PyException;
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::types::PyAnyMethods;
+use pyo3::types::PyModule;
 use pyo3::types::PyTracebackMethods;
+use pyo3::Bound;
 use pyo3::PyDowncastError;
 use pyo3::PyErr;
 use pyo3::PyResult;
 use pyo3::PyTypeInfo;
 use pyo3::Python;
 
+create_exception!(
+    bytewax.errors,
+    BytewaxInternalError,
+    PyRuntimeError,
+    "Raised when Bytewax detects a broken internal invariant.
+
+Unlike most other exceptions, this does not originate from your
+callbacks raising or returning bad data; it means Bytewax itself got
+into a state it should never be in. Please [report
+it](https://github.com/bytewax/bytewax/issues) including the full
+traceback."
+);
+
+/// Whether `raise_with`/`reraise_with` should skip building their
+/// formatted context message and the traceback of the wrapped error.
+///
+/// On workloads where errors are common (e.g. a step that catches
+/// and counts many per-item failures), formatting a fresh context
+/// string and rendering a traceback for every single one is
+/// significant overhead compared to raising the error itself. Set
+/// `BYTEWAX_ERROR_VERBOSITY=fast` to skip both and only keep the
+/// step-and-caller-identifying prefix. Checked once and cached, since
+/// this isn't something you'd toggle mid-run.
+fn fast_errors() -> bool {
+    static FAST: OnceLock<bool> = OnceLock::new();
+    *FAST.get_or_init(|| {
+        std::env::var("BYTEWAX_ERROR_VERBOSITY")
+            .map(|v| v.eq_ignore_ascii_case("fast"))
+            .unwrap_or(false)
+    })
+}
+
+/// Stand-in for a caller's context-message closure in fast mode, so
+/// we never call into it and pay for whatever reflection or
+/// formatting it does (e.g. `.get_type().name()`).
+fn f_placeholder() -> String {
+    "(context omitted; set BYTEWAX_ERROR_VERBOSITY=full to see it)".to_string()
+}
+
 /// A trait to build a python exception with a custom stacktrace from
 /// anything that can be converted into a PyResult.
 pub(crate) trait PythonException<T> {
@@ -51,7 +94,7 @@ pub(crate) trait PythonException<T> {
     {
         let caller = Location::caller();
         self.into_pyresult().map_err(|err| {
-            let msg = f();
+            let msg = if fast_errors() { f_placeholder() } else { f() };
             Python::with_gil(|py| PyErr::new::<PyErrType, _>(build_message(py, caller, &err, &msg)))
         })
     }
@@ -114,7 +157,7 @@ pub(crate) trait PythonException<T> {
     {
         let caller = Location::caller();
         self.into_pyresult().map_err(|err| {
-            let msg = f();
+            let msg = if fast_errors() { f_placeholder() } else { f() };
             Python::with_gil(|py| {
                 // Python treats KeyError differently then others:
                 // the message is always quoted, so that in case the key
@@ -195,9 +238,21 @@ pub(crate) fn tracked_err<PyErrType: PyTypeInfo>(msg: &str) -> PyErr {
     PyErr::new::<PyErrType, _>(prepend_caller(caller, msg))
 }
 
+pub(crate) fn register(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add(
+        "BytewaxInternalError",
+        py.get_type_bound::<BytewaxInternalError>(),
+    )?;
+    Ok(())
+}
+
 fn build_message(py: Python, caller: &Location, err: &PyErr, msg: &str) -> String {
     let msg = prepend_caller(caller, msg);
 
+    if fast_errors() {
+        return format!("{msg}\nCaused by => {err}");
+    }
+
     let err_msg = get_traceback(py, err)
         .map(|tb| format!("{err}\n{tb}"))
         .unwrap_or_else(|| format!("{err}"));
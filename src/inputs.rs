@@ -241,6 +241,7 @@ impl FixedPartitionedSource {
         epoch_interval: EpochInterval,
         probe: &ProbeHandle<u64>,
         abort: &Arc<AtomicBool>,
+        drain: &'static AtomicBool,
         start_at: ResumeEpoch,
         loads: &Stream<S, Snapshot>,
     ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, Snapshot>)>
@@ -253,7 +254,8 @@ impl FixedPartitionedSource {
             format!("error calling `FixedPartitionSource.list_parts` in step {step_id}")
         })?;
         let all_parts = local_parts.into_broadcast(scope, S::Timestamp::minimum());
-        let primary_updates = all_parts.assign_primaries(format!("{step_id}.assign_primaries"));
+        let primary_updates =
+            all_parts.assign_primaries(format!("{step_id}.assign_primaries"), None);
 
         let routed_loads = loads
             .filter_snaps(step_id.clone())
@@ -437,12 +439,24 @@ impl FixedPartitionedSource {
                             // backpressure.
                             if !probe.less_than(&epoch) {
                                 let mut eof = false;
-                                // Separately check wheither we should
-                                // call `next_batch` because we need
-                                // to keep advancing the epoch for
-                                // this input, even if it hasn't been
-                                // awoken to prevent dataflow stall.
-                                if part_state.awake_due(now) {
+                                // If a graceful drain was requested,
+                                // stop calling `next_batch` and treat
+                                // this partition as EOF so downstream
+                                // stateful steps get to run `on_eof`
+                                // and take a final snapshot instead of
+                                // the dataflow being torn down
+                                // mid-epoch.
+                                if drain.load(atomic::Ordering::Relaxed) {
+                                    eof = true;
+                                    eofd_parts_buffer.push(part_key.clone());
+                                    tracing::debug!("Draining");
+                                } else if part_state.awake_due(now) {
+                                    // Separately check wheither we
+                                    // should call `next_batch` because
+                                    // we need to keep advancing the
+                                    // epoch for this input, even if it
+                                    // hasn't been awoken to prevent
+                                    // dataflow stall.
                                     unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
                                         let batch_res = with_timer!(
                                             next_batch_histogram,
@@ -691,6 +705,7 @@ impl DynamicSource {
         epoch_interval: EpochInterval,
         probe: &ProbeHandle<u64>,
         abort: &Arc<AtomicBool>,
+        drain: &'static AtomicBool,
         start_at: ResumeEpoch,
     ) -> PyResult<Stream<S, TdPyAny>>
     where
@@ -758,12 +773,21 @@ impl DynamicSource {
                         // finished the previous epoch before emitting
                         // more data to have backpressure.
                         if !probe.less_than(epoch) {
-                            // Separately check wheither we should
-                            // call `next_batch` because we need to
-                            // keep advancing the epoch for this
-                            // input, even if it hasn't been awoken to
-                            // prevent dataflow stall.
-                            if part_state.awake_due(now) {
+                            // If a graceful drain was requested, stop
+                            // calling `next_batch` and treat this
+                            // partition as EOF so downstream stateful
+                            // steps get to run `on_eof` and take a
+                            // final snapshot instead of the dataflow
+                            // being torn down mid-epoch.
+                            if drain.load(atomic::Ordering::Relaxed) {
+                                eof = true;
+                                tracing::trace!("Draining");
+                            } else if part_state.awake_due(now) {
+                                // Separately check wheither we should
+                                // call `next_batch` because we need to
+                                // keep advancing the epoch for this
+                                // input, even if it hasn't been
+                                // awoken to prevent dataflow stall.
                                 unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
                                     let res = with_timer!(
                                         next_batch_histogram,
@@ -24,6 +24,7 @@ pub(crate) mod macros;
 #[pymodule]
 #[pyo3(name = "_bytewax")]
 fn mod_bytewax(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    errors::register(py, m)?;
     inputs::register(py, m)?;
     recovery::register(py, m)?;
     run::register(py, m)?;
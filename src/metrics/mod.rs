@@ -1,9 +1,12 @@
+use std::time::Duration;
+
 use opentelemetry::{
     global,
     sdk::metrics::{Aggregation, Instrument, MeterProvider, Stream},
 };
 use prometheus::default_registry;
-use pyo3::{exceptions::PyRuntimeError, PyErr, PyResult};
+use pyo3::types::{PyAnyMethods, PyByteArray, PyBytes};
+use pyo3::{exceptions::PyRuntimeError, Py, PyAny, PyErr, PyResult, Python};
 
 #[macro_export]
 macro_rules! with_timer {
@@ -15,6 +18,61 @@ macro_rules! with_timer {
     }};
 }
 
+/// Like [`pyo3::Python::with_gil`], but records the time spent
+/// waiting to acquire the GIL (not the time spent running `$closure`
+/// itself) into `$histogram`, labeled by `$labels`.
+///
+/// Every Python-calling operator contends for the single
+/// process-wide GIL, so this approximates how much a given step is
+/// suffering from contention with other operators on the same
+/// worker. Use [`crate::with_timer`] around the body instead if you
+/// want to time the call itself rather than the wait to get in.
+#[macro_export]
+macro_rules! with_gil_timed {
+    ($histogram: expr, $labels: expr, $closure: expr) => {{
+        let gil_wait_start = std::time::Instant::now();
+        pyo3::Python::with_gil(|py| {
+            $histogram.record(gil_wait_start.elapsed().as_secs_f64(), &$labels);
+            ($closure)(py)
+        })
+    }};
+}
+
+/// Run `$body`, logging a `tracing::error!` and incrementing
+/// `$counter` if it's still running after
+/// [`crate::metrics::callback_timeout`], since `$desc` describes a
+/// call into Python code we can't safely interrupt while it holds
+/// the GIL.
+///
+/// A no-op, spawning no watcher thread, if no timeout is configured.
+#[macro_export]
+macro_rules! with_watchdog {
+    ($counter: expr, $labels: expr, $desc: expr, $body: expr) => {{
+        match $crate::metrics::callback_timeout() {
+            Some(timeout) => {
+                let hung = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+                let watcher_hung = std::sync::Arc::clone(&hung);
+                let counter = $counter.clone();
+                let labels = $labels.clone();
+                let desc = $desc;
+                std::thread::spawn(move || {
+                    std::thread::sleep(timeout);
+                    if watcher_hung.load(std::sync::atomic::Ordering::SeqCst) {
+                        tracing::error!(
+                            "{desc} has been running for over {timeout:?}, it may be hung"
+                        );
+                        counter.add(1, &labels);
+                    }
+                });
+                let res = $body;
+                hung.store(false, std::sync::atomic::Ordering::SeqCst);
+                res
+            }
+            None => $body,
+        }
+    }};
+}
+
 /// Initialize the global registry for Prometheus metrics,
 /// and create a global MeterProvider.
 pub(crate) fn initialize_metrics() -> PyResult<()> {
@@ -47,3 +105,59 @@ pub(crate) fn initialize_metrics() -> PyResult<()> {
     global::set_meter_provider(provider);
     Ok(())
 }
+
+/// Whether operators should also record how many bytes of items they
+/// process, via an `item_inp_bytes` counter alongside `item_inp_count`.
+///
+/// This means checking every item's type and calling `len()` on the
+/// bytes-like ones, which is extra per-item overhead most runs don't
+/// need, so it's opt-in. Set `BYTEWAX_ITEM_SIZE_METRIC=true` to
+/// enable it. Checked once and cached, since this isn't something
+/// you'd toggle mid-run.
+pub(crate) fn item_size_metric_enabled() -> bool {
+    static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var("BYTEWAX_ITEM_SIZE_METRIC")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    })
+}
+
+/// How long a Python callback (e.g.
+/// {py:obj}`~bytewax.operators.StatefulBatchLogic.on_batch` or a
+/// sink's `write_batch`) is allowed to run before
+/// [`crate::with_watchdog`] considers it hung and logs a diagnostic.
+///
+/// We can't safely interrupt a call that's holding the GIL, so this
+/// only buys visibility, not a real timeout. Spawning a watcher
+/// thread per call has a real cost, so this is opt-in via
+/// `BYTEWAX_CALLBACK_TIMEOUT_SECONDS`; unset disables the watchdog
+/// entirely. Checked once and cached, since this isn't something
+/// you'd toggle mid-run.
+pub(crate) fn callback_timeout() -> Option<Duration> {
+    static TIMEOUT: std::sync::OnceLock<Option<Duration>> = std::sync::OnceLock::new();
+    *TIMEOUT.get_or_init(|| {
+        std::env::var("BYTEWAX_CALLBACK_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|secs| *secs > 0.0)
+            .map(Duration::from_secs_f64)
+    })
+}
+
+/// Byte length of `obj` if it's `bytes` or `bytearray`, otherwise
+/// `0`.
+///
+/// Backs the `item_inp_bytes` counter without requiring a
+/// user-provided sizer callback; only ever a cheap FFI `len()`
+/// call, never a call into Python code.
+pub(crate) fn bytes_like_len(py: Python, obj: &Py<PyAny>) -> u64 {
+    let obj = obj.bind(py);
+    if let Ok(b) = obj.downcast::<PyBytes>() {
+        b.as_bytes().len() as u64
+    } else if let Ok(b) = obj.downcast::<PyByteArray>() {
+        b.len() as u64
+    } else {
+        0
+    }
+}
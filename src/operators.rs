@@ -1,18 +1,36 @@
 //! Code implementing Bytewax's core operators.
 
-use std::collections::hash_map::DefaultHasher;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::File;
+use std::hash::BuildHasher;
 use std::hash::BuildHasherDefault;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Instant;
 
 use chrono::DateTime;
+use chrono::TimeDelta;
 use chrono::Utc;
 use opentelemetry::KeyValue;
+use pyo3::exceptions::PyRuntimeError;
 use pyo3::exceptions::PyTypeError;
+use pyo3::exceptions::PyValueError;
 use pyo3::intern;
 use pyo3::prelude::*;
+use seahash::SeaHasher;
 use timely::dataflow::channels::pact::Pipeline;
 use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
+use timely::dataflow::operators::Broadcast;
 use timely::dataflow::operators::Concatenate;
 use timely::dataflow::operators::Exchange;
 use timely::dataflow::operators::Map;
@@ -23,13 +41,17 @@ use timely::order::TotalOrder;
 use timely::progress::Antichain;
 use timely::ExchangeData;
 
+use crate::errors::tracked_err;
 use crate::errors::PythonException;
+use crate::metrics::bytes_like_len;
+use crate::metrics::item_size_metric_enabled;
 use crate::pyo3_extensions::TdPyAny;
 use crate::pyo3_extensions::TdPyCallable;
 use crate::recovery::*;
 use crate::timely::*;
 use crate::unwrap_any;
 use crate::with_timer;
+use crate::with_watchdog;
 
 pub(crate) trait BranchOp<S>
 where
@@ -39,6 +61,7 @@ where
         &self,
         step_id: StepId,
         predicate: TdPyCallable,
+        ordered: bool,
     ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>)>;
 }
 
@@ -50,6 +73,7 @@ where
         &self,
         step_id: StepId,
         predicate: TdPyCallable,
+        ordered: bool,
     ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>)> {
         let mut op_builder = OperatorBuilder::new(format!("{step_id}.branch"), self.scope());
 
@@ -59,6 +83,10 @@ where
 
         op_builder.build(move |_| {
             let mut inbuf = Vec::new();
+            // Only advances when `ordered`, so `unbranch` can recover
+            // the original interleaving of `trues` and `falses` even
+            // after they're processed independently downstream.
+            let mut seq: u64 = 0;
             move |_frontiers| {
                 let mut trues_handle = trues_output.activate();
                 let mut falses_handle = falses_output.activate();
@@ -82,6 +110,16 @@ where
                                         "return value of `predicate` in step {step_id} must be a `bool`"
                                     )
                                     })?;
+                                let item = if ordered {
+                                    let stamped = IntoPy::<PyObject>::into_py(
+                                        (PyObject::from(item), seq),
+                                        py,
+                                    );
+                                    seq += 1;
+                                    TdPyAny::from(stamped)
+                                } else {
+                                    item
+                                };
                                 if res {
                                     trues_session.give(item);
                                 } else {
@@ -99,26 +137,455 @@ where
     }
 }
 
+pub(crate) trait UnbranchOp<S>
+where
+    S: Scope,
+{
+    /// Recombine two `ordered` [`BranchOp::branch`] outputs, restoring
+    /// their original interleaving.
+    fn unbranch(
+        &self,
+        step_id: StepId,
+        falses: &Stream<S, TdPyAny>,
+    ) -> PyResult<Stream<S, TdPyAny>>;
+}
+
+impl<S> UnbranchOp<S> for Stream<S, TdPyAny>
+where
+    S: Scope<Timestamp = u64>,
+{
+    fn unbranch(
+        &self,
+        step_id: StepId,
+        falses: &Stream<S, TdPyAny>,
+    ) -> PyResult<Stream<S, TdPyAny>> {
+        let mut op_builder = OperatorBuilder::new(step_id.0.clone(), self.scope());
+
+        let mut trues_handle = op_builder.new_input(self, Pipeline);
+        let mut falses_handle = op_builder.new_input(falses, Pipeline);
+        let (mut downstream_output, downstream) = op_builder.new_output();
+
+        op_builder.build(move |init_caps| {
+            let mut trues_inbuf = InBuffer::new();
+            let mut falses_inbuf = InBuffer::new();
+            // Items seen for the epoch currently being assembled,
+            // keyed by the `seq` stamped on them at `branch` time;
+            // iterating a `BTreeMap` walks them back in that order.
+            let pending: BTreeMap<u64, TdPyAny> = BTreeMap::new();
+            let mut ncater = EagerNotificator::new(init_caps, pending);
+
+            move |input_frontiers| {
+                tracing::debug_span!("operator", operator = step_id.0.clone()).in_scope(|| {
+                    trues_handle.buffer_notify(&mut trues_inbuf, &mut ncater);
+                    falses_handle.buffer_notify(&mut falses_inbuf, &mut ncater);
+
+                    let mut downstream_handle = downstream_output.activate();
+
+                    ncater.for_each(
+                        input_frontiers,
+                        |caps, pending| {
+                            let cap = &caps[0];
+                            let epoch = cap.time();
+
+                            Python::with_gil(|py| {
+                                unwrap_any!(|| -> PyResult<()> {
+                                    for items in
+                                        [trues_inbuf.remove(epoch), falses_inbuf.remove(epoch)]
+                                            .into_iter()
+                                            .flatten()
+                                    {
+                                        for item in items {
+                                            let bound = item.bind(py);
+                                            let (value, seq): (PyObject, u64) =
+                                                bound.extract().reraise_with(|| {
+                                                    format!(
+                                                    "in step {step_id}, `unbranch` expects items \
+                                                    stamped by an `ordered=True` branch, i.e. \
+                                                    `(value, seq)` pairs, but got a \
+                                                    `{}`; did you forget `ordered=True` or mix in \
+                                                    a stream that wasn't produced by `branch`?",
+                                                    unwrap_any!(bound.get_type().name())
+                                                )
+                                                })?;
+                                            pending.insert(seq, TdPyAny::from(value));
+                                        }
+                                    }
+                                    Ok(())
+                                }());
+                            });
+                        },
+                        |caps, pending| {
+                            let cap = &caps[0];
+                            let mut session = downstream_handle.session(cap);
+                            for (_seq, item) in std::mem::take(pending) {
+                                session.give(item);
+                            }
+                        },
+                    );
+                });
+            }
+        });
+
+        Ok(downstream)
+    }
+}
+
+pub(crate) trait BranchBatchOp<S>
+where
+    S: Scope,
+{
+    fn branch_batch(
+        &self,
+        step_id: StepId,
+        predicate: TdPyCallable,
+    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>)>;
+}
+
+impl<S> BranchBatchOp<S> for Stream<S, TdPyAny>
+where
+    S: Scope,
+{
+    fn branch_batch(
+        &self,
+        step_id: StepId,
+        predicate: TdPyCallable,
+    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>)> {
+        let mut op_builder = OperatorBuilder::new(format!("{step_id}.branch_batch"), self.scope());
+
+        let mut self_handle = op_builder.new_input(self, Pipeline);
+        let (mut trues_output, trues) = op_builder.new_output();
+        let (mut falses_output, falses) = op_builder.new_output();
+
+        op_builder.build(move |_| {
+            let mut inbuf = Vec::new();
+            move |_frontiers| {
+                let mut trues_handle = trues_output.activate();
+                let mut falses_handle = falses_output.activate();
+
+                Python::with_gil(|py| {
+                    self_handle.for_each(|time, data| {
+                        data.swap(&mut inbuf);
+                        let mut trues_session = trues_handle.session(&time);
+                        let mut falses_session = falses_handle.session(&time);
+                        let pred = predicate.bind(py);
+                        unwrap_any!(|| -> PyResult<()> {
+                            let batch: Vec<PyObject> =
+                                inbuf.iter().map(|item| item.bind(py).to_object(py)).collect();
+                            let results = pred
+                                .call1((batch,))
+                                .reraise_with(|| {
+                                    format!("error calling predicate in step {step_id}")
+                                })?
+                                .extract::<Vec<bool>>()
+                                .reraise_with(|| {
+                                    format!(
+                                        "return value of `predicate` in step {step_id} \
+                                        must be a `list` of `bool`, one per input item"
+                                    )
+                                })?;
+                            if results.len() != inbuf.len() {
+                                let msg = format!(
+                                    "`predicate` in step {step_id} returned {} value(s) \
+                                    but was called with {} item(s); it must return exactly \
+                                    one `bool` per item",
+                                    results.len(),
+                                    inbuf.len()
+                                );
+                                return Err(tracked_err::<PyValueError>(&msg));
+                            }
+                            for (item, is_true) in inbuf.drain(..).zip(results) {
+                                if is_true {
+                                    trues_session.give(item);
+                                } else {
+                                    falses_session.give(item);
+                                }
+                            }
+                            Ok(())
+                        }());
+                    })
+                });
+            }
+        });
+
+        Ok((trues, falses))
+    }
+}
+
+/// Owns the event loop `next_batch` drives an async `mapper`'s
+/// awaitable or async generator return value on.
+///
+/// This is built once per operator instance, not per activation, so
+/// mappers that never return anything awaitable pay no per-batch
+/// cost, and one running event loop is reused across batches the
+/// same way [`crate::recovery::SerializeSnapshotOp::ser_snap`] reuses
+/// a single imported `pickle` module.
+///
+/// Note this only lets a single `mapper` call finish its I/O before
+/// the next one starts; the operator still runs each activation to
+/// completion synchronously; like the rest of the dataflow, it isn't
+/// woken back up mid-await, so other keys/steps do not make progress
+/// while a mapper is awaiting. Actually overlapping I/O across
+/// batches would mean restructuring this operator around Timely's
+/// activator so it can suspend and resume across activations, which
+/// is out of scope here.
+struct AsyncMapperCtx {
+    event_loop: Py<PyAny>,
+    drain: Py<PyAny>,
+}
+
+impl AsyncMapperCtx {
+    fn new(py: Python) -> PyResult<Self> {
+        let asyncio = py.import_bound("asyncio")?;
+        let event_loop = asyncio.call_method0("new_event_loop")?;
+        let module = PyModule::from_code_bound(
+            py,
+            "async def _drain(obj):\n\
+             \x20   if hasattr(obj, \"__anext__\"):\n\
+             \x20       return [item async for item in obj]\n\
+             \x20   return list(await obj)\n",
+            "_bytewax_async_mapper.py",
+            "_bytewax_async_mapper",
+        )?;
+        Ok(Self {
+            event_loop: event_loop.unbind(),
+            drain: module.getattr("_drain")?.unbind(),
+        })
+    }
+
+    /// If `res` is awaitable or an async generator, run it to
+    /// completion on our event loop and return the collected items.
+    /// Otherwise return `None` to signal the caller should treat
+    /// `res` as an already-synchronous iterable.
+    fn drain_if_async<'py>(
+        &self,
+        py: Python<'py>,
+        res: &Bound<'py, PyAny>,
+    ) -> PyResult<Option<Bound<'py, PyAny>>> {
+        if !res.hasattr("__await__")? && !res.hasattr("__anext__")? {
+            return Ok(None);
+        }
+        let coro = self.drain.bind(py).call1((res,))?;
+        let items = self
+            .event_loop
+            .bind(py)
+            .call_method1("run_until_complete", (coro,))?;
+        Ok(Some(items))
+    }
+}
+
+/// Threshold and directory for spilling [`flat_map_batch`]'s
+/// per-batch output to disk once it grows large, instead of holding
+/// every output object live in memory at once.
+///
+/// Off by default, since spilling means pickling and writing every
+/// output past the threshold instead of just holding a reference to
+/// it, trading throughput for surviving a mapper with pathological
+/// fan-out. Set `BYTEWAX_FLAT_MAP_BATCH_SPILL_THRESHOLD_BYTES=<bytes>`
+/// to turn it on; `BYTEWAX_FLAT_MAP_BATCH_SPILL_DIR` optionally
+/// overrides where the temporary spill files are created, defaulting
+/// to the system temp directory. Checked once and cached, since this
+/// isn't something you'd toggle mid-run.
+fn spill_config() -> &'static Option<(u64, PathBuf)> {
+    static CONFIG: OnceLock<Option<(u64, PathBuf)>> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let threshold_bytes = std::env::var("BYTEWAX_FLAT_MAP_BATCH_SPILL_THRESHOLD_BYTES")
+            .ok()?
+            .parse()
+            .ok()?;
+        let dir = std::env::var("BYTEWAX_FLAT_MAP_BATCH_SPILL_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| std::env::temp_dir());
+        Some((threshold_bytes, dir))
+    })
+}
+
+/// Spills accumulated [`flat_map_batch`] output to a temporary file
+/// past a byte threshold, so a mapper that's still producing more
+/// output doesn't have to keep everything it's already produced live
+/// in memory at the same time.
+///
+/// Every spilled item is read back into memory right before being
+/// handed to Timely, since [`flat_map_batch`] still emits one `Vec`
+/// per batch; this only bounds the memory held while a single
+/// pathologically fan-out-y mapper call is still running, not the
+/// eventual handoff.
+struct OutbufSpiller {
+    writer: BufWriter<File>,
+    path: PathBuf,
+}
+
+impl OutbufSpiller {
+    fn create(dir: &Path, step_id: &StepId) -> std::io::Result<Self> {
+        static UNIQUE: AtomicU64 = AtomicU64::new(0);
+        let unique = UNIQUE.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!(
+            "bytewax-flat-map-batch-spill-{}-{}-{unique}.pickle",
+            std::process::id(),
+            step_id.0,
+        ));
+        let file = File::create(&path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            path,
+        })
+    }
+
+    /// Append an already-pickled item to the spill file,
+    /// length-prefixed so [`Self::drain_into`] knows where each one
+    /// ends.
+    fn write(&mut self, pickled: &[u8]) -> PyResult<()> {
+        self.writer
+            .write_all(&(pickled.len() as u64).to_le_bytes())
+            .reraise("error spilling flat_map_batch output")?;
+        self.writer
+            .write_all(pickled)
+            .reraise("error spilling flat_map_batch output")
+    }
+
+    /// Read every spilled item back, in the order it was written,
+    /// appending it to `outbuf`, then delete the spill file.
+    fn drain_into(self, py: Python, outbuf: &mut Vec<TdPyAny>) -> PyResult<()> {
+        let Self { mut writer, path } = self;
+        writer
+            .flush()
+            .reraise("error flushing flat_map_batch spill file")?;
+        drop(writer);
+
+        let mut file = File::open(&path).reraise("error reopening flat_map_batch spill file")?;
+        let mut len_buf = [0; 8];
+        loop {
+            match file.read_exact(&mut len_buf) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(err) => {
+                    return Err(err).reraise("error reading flat_map_batch spill file");
+                }
+            }
+            let mut bytes = vec![0; u64::from_le_bytes(len_buf) as usize];
+            file.read_exact(&mut bytes)
+                .reraise("error reading flat_map_batch spill file")?;
+            outbuf.push(pickle_loads(py, &bytes)?);
+        }
+
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
+}
+
+/// Pickle a single item to bytes, the same way [`TdPyAny`]'s own
+/// `Serialize` impl does for recovery snapshots.
+fn pickle_dumps(py: Python, item: &TdPyAny) -> PyResult<Vec<u8>> {
+    let bytes = py
+        .import_bound("pickle")?
+        .call_method1("dumps", (item.bind(py),))?;
+    Ok(bytes.downcast::<pyo3::types::PyBytes>()?.as_bytes().to_vec())
+}
+
+/// Unpickle a single item from bytes written by [`pickle_dumps`].
+fn pickle_loads(py: Python, bytes: &[u8]) -> PyResult<TdPyAny> {
+    let obj = py
+        .import_bound("pickle")?
+        .call_method1("loads", (bytes,))?;
+    Ok(obj.unbind().into())
+}
+
+/// Runs `mapper` once over `in_batch` and appends its outputs to
+/// `outbuf` in the order the mapper returned them, which is also the
+/// order `in_batch`'s items were given in: `mapper` is called once
+/// with the whole batch and `outbuf` is appended to by iterating its
+/// single return value in order, so everything returned for item `i`
+/// always lands before anything returned for item `i + 1`. Keep this
+/// true if this ever becomes concurrent across sub-batches.
+///
+/// `epoch`, if given, is passed as a second positional argument, for
+/// mappers that declared a 2-argument signature.
+///
+/// If `BYTEWAX_FLAT_MAP_BATCH_SPILL_THRESHOLD_BYTES` is set,
+/// `outbuf`'s accumulated outputs are spilled to a temporary file
+/// once they cross that size, and streamed back into `outbuf` right
+/// before returning; see [`OutbufSpiller`].
 fn next_batch(
     outbuf: &mut Vec<TdPyAny>,
     mapper: &Bound<'_, PyAny>,
+    async_ctx: &AsyncMapperCtx,
     in_batch: Vec<PyObject>,
+    epoch: Option<PyObject>,
+    step_id: &StepId,
 ) -> PyResult<()> {
-    let res = mapper.call1((in_batch,)).reraise("error calling mapper")?;
+    let py = mapper.py();
+    let res = match epoch {
+        Some(epoch) => mapper.call1((in_batch, epoch)),
+        None => mapper.call1((in_batch,)),
+    }
+    .reraise("error calling mapper")?;
+    let res = match async_ctx.drain_if_async(py, &res)? {
+        Some(items) => items,
+        None => res,
+    };
     let iter = res.iter().reraise_with(|| {
         format!(
-            "mapper must return an iterable; got a `{}` instead",
+            "mapper must return an iterable, awaitable, or async generator; \
+            got a `{}` instead",
             unwrap_any!(res.get_type().name()),
         )
     })?;
+
+    let mut spiller: Option<OutbufSpiller> = None;
+    let mut pending_bytes: u64 = 0;
     for res in iter {
-        let out_item = res.reraise("error while iterating through batch")?;
-        outbuf.push(out_item.into());
+        let out_item: TdPyAny = res.reraise("error while iterating through batch")?.into();
+
+        if let Some((threshold_bytes, dir)) = spill_config() {
+            let pickled = pickle_dumps(py, &out_item)?;
+            pending_bytes += pickled.len() as u64;
+
+            if spiller.is_none() && pending_bytes > *threshold_bytes {
+                let mut new_spiller = OutbufSpiller::create(dir, step_id)
+                    .reraise("error creating flat_map_batch spill file")?;
+                for item in outbuf.drain(..) {
+                    new_spiller.write(&pickle_dumps(py, &item)?)?;
+                }
+                spiller = Some(new_spiller);
+            }
+
+            if let Some(spiller) = &mut spiller {
+                spiller.write(&pickled)?;
+                continue;
+            }
+        }
+
+        outbuf.push(out_item);
+    }
+
+    if let Some(spiller) = spiller {
+        spiller.drain_into(py, outbuf)?;
     }
 
     Ok(())
 }
 
+/// Whether `flat_map_batch` should time each batch from the moment
+/// it's received to the moment its outputs are emitted, and record
+/// that duration once per output item as
+/// `flat_map_batch_item_latency_seconds`.
+///
+/// This is distinct from `flat_map_batch_duration_seconds`, which
+/// only times the `mapper` call itself; this also captures time
+/// spent waiting in `inbuf` for its epoch to be safe to process.
+/// Recording it requires an extra `Instant::now()` and a histogram
+/// observation per output item rather than once per batch, so it's
+/// opt-in. Set `BYTEWAX_FLAT_MAP_BATCH_ITEM_LATENCY=true` to enable
+/// it. Checked once and cached, since this isn't something you'd
+/// toggle mid-run.
+fn item_latency_enabled() -> bool {
+    static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var("BYTEWAX_FLAT_MAP_BATCH_ITEM_LATENCY")
+            .map(|v| v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+    })
+}
+
 pub(crate) trait FlatMapBatchOp<S>
 where
     S: Scope,
@@ -135,15 +602,28 @@ where
 impl<S> FlatMapBatchOp<S> for Stream<S, TdPyAny>
 where
     S: Scope,
-    S::Timestamp: TotalOrder,
+    S::Timestamp: IntoPy<PyObject> + TotalOrder,
 {
     fn flat_map_batch(
         &self,
-        _py: Python,
+        py: Python,
         step_id: StepId,
         mapper: TdPyCallable,
     ) -> PyResult<Stream<S, TdPyAny>> {
         let this_worker = self.scope().w_index();
+        let async_ctx = AsyncMapperCtx::new(py).reraise("error setting up async mapper support")?;
+
+        // For a mapper that also wants to know the current epoch,
+        // e.g. to tag its outputs with it, only pass it if `mapper`
+        // declares a 2nd parameter to receive it. Mappers written
+        // against the old 1-argument signature are called exactly as
+        // before.
+        let mapper_wants_epoch = py
+            .import_bound("inspect")?
+            .call_method1("signature", (mapper.bind(py),))?
+            .getattr("parameters")?
+            .len()?
+            >= 2;
 
         let mut op_builder = OperatorBuilder::new(step_id.0.clone(), self.scope());
 
@@ -160,10 +640,29 @@ where
             .u64_counter("item_out_count")
             .with_description("number of items this step has emitted")
             .init();
+        let item_inp_bytes = meter
+            .u64_counter("item_inp_bytes")
+            .with_description(
+                "total byte length of ingested items that are `bytes` or \
+                `bytearray`; only recorded when BYTEWAX_ITEM_SIZE_METRIC=true",
+            )
+            .init();
         let mapper_histogram = meter
             .f64_histogram("flat_map_batch_duration_seconds")
             .with_description("`flat_map_batch` `mapper` duration in seconds")
             .init();
+        let gil_wait_histogram = meter
+            .f64_histogram("gil_wait_duration_seconds")
+            .with_description("time spent waiting to acquire the GIL")
+            .init();
+        let item_latency_histogram = meter
+            .f64_histogram("flat_map_batch_item_latency_seconds")
+            .with_description(
+                "end-to-end latency from a batch being received to each of its \
+                outputs being emitted, in seconds; only recorded when \
+                BYTEWAX_FLAT_MAP_BATCH_ITEM_LATENCY=true",
+            )
+            .init();
         let labels = vec![
             KeyValue::new("step_id", step_id.0.to_string()),
             KeyValue::new("worker_index", this_worker.0.to_string()),
@@ -181,7 +680,13 @@ where
             let mut outbuf = Vec::new();
 
             move |input_frontiers| {
-                tracing::debug_span!("operator", operator = step_id.0.clone()).in_scope(|| {
+                let span = tracing::debug_span!(
+                    "operator",
+                    operator = step_id.0.clone(),
+                    epoch = tracing::field::Empty,
+                    item_count = tracing::field::Empty,
+                );
+                span.in_scope(|| {
                     self_handle.buffer_notify(&mut inbuf, &mut ncater);
 
                     let mut downstream_handle = downstream_output.activate();
@@ -192,29 +697,55 @@ where
                             let epoch = cap.time();
 
                             if let Some(batch) = inbuf.remove(epoch) {
+                                span.record("epoch", tracing::field::debug(epoch));
+                                span.record("item_count", batch.len());
                                 item_inp_count.add(batch.len() as u64, &labels);
+                                let batch_recv_at = item_latency_enabled().then(Instant::now);
                                 let mut downstream_session = downstream_handle.session(cap);
 
-                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
-                                    let batch: Vec<_> =
-                                        batch.into_iter().map(PyObject::from).collect();
-                                    let mapper = mapper.bind(py);
-
-                                    with_timer!(
-                                        mapper_histogram,
-                                        labels,
-                                        next_batch(&mut outbuf, mapper, batch).reraise_with(
-                                            || {
+                                unwrap_any!(with_gil_timed!(
+                                    gil_wait_histogram,
+                                    labels,
+                                    |py| -> PyResult<()> {
+                                        let batch: Vec<_> =
+                                            batch.into_iter().map(PyObject::from).collect();
+                                        if item_size_metric_enabled() {
+                                            let inp_bytes: u64 =
+                                                batch.iter().map(|item| bytes_like_len(py, item)).sum();
+                                            item_inp_bytes.add(inp_bytes, &labels);
+                                        }
+                                        let mapper = mapper.bind(py);
+                                        let epoch_arg =
+                                            mapper_wants_epoch.then(|| epoch.clone().into_py(py));
+
+                                        with_timer!(
+                                            mapper_histogram,
+                                            labels,
+                                            next_batch(
+                                                &mut outbuf,
+                                                mapper,
+                                                &async_ctx,
+                                                batch,
+                                                epoch_arg,
+                                                &step_id,
+                                            )
+                                            .reraise_with(|| {
                                                 format!("error calling `mapper` in step {step_id}")
-                                            }
-                                        )?
-                                    );
+                                            })?
+                                        );
 
-                                    item_out_count.add(outbuf.len() as u64, &labels);
-                                    downstream_session.give_vec(&mut outbuf);
+                                        item_out_count.add(outbuf.len() as u64, &labels);
+                                        if let Some(batch_recv_at) = batch_recv_at {
+                                            let latency = batch_recv_at.elapsed().as_secs_f64();
+                                            for _ in 0..outbuf.len() {
+                                                item_latency_histogram.record(latency, &labels);
+                                            }
+                                        }
+                                        downstream_session.give_vec(&mut outbuf);
 
-                                    Ok(())
-                                }));
+                                        Ok(())
+                                    }
+                                ));
                             }
                         },
                         |_caps, ()| {},
@@ -231,11 +762,16 @@ pub(crate) trait InspectDebugOp<S>
 where
     S: Scope,
 {
+    /// If `heartbeat` is `True`, the clock stream ticks every epoch,
+    /// even ones with no items. Otherwise it only ticks epochs that
+    /// had items, which can stall probe-driven backpressure elsewhere
+    /// in the dataflow if this operator's upstream goes idle.
     fn inspect_debug(
         &self,
         py: Python,
         step_id: StepId,
         inspector: TdPyCallable,
+        heartbeat: bool,
     ) -> PyResult<(Stream<S, TdPyAny>, ClockStream<S>)>;
 }
 
@@ -246,12 +782,24 @@ where
 {
     fn inspect_debug(
         &self,
-        _py: Python,
+        py: Python,
         step_id: StepId,
         inspector: TdPyCallable,
+        heartbeat: bool,
     ) -> PyResult<(Stream<S, TdPyAny>, ClockStream<S>)> {
         let this_worker = self.scope().w_index();
 
+        // For backward compatibility with inspectors written against
+        // the old `(step_id, item, epoch, worker)` signature, only
+        // pass the input frontier epoch if `inspector` declares a
+        // 5th parameter to receive it.
+        let wants_frontier = py
+            .import_bound("inspect")?
+            .call_method1("signature", (inspector.bind(py),))?
+            .getattr("parameters")?
+            .len()?
+            >= 5;
+
         let mut op_builder = OperatorBuilder::new(step_id.0.clone(), self.scope());
 
         let mut self_handle = op_builder.new_input(self, Pipeline);
@@ -267,8 +815,17 @@ where
                 tracing::debug_span!("operator", operator = step_id.0.clone()).in_scope(|| {
                     self_handle.buffer_notify(&mut items_inbuf, &mut ncater);
 
+                    if heartbeat {
+                        if let Some(epoch) = input_frontiers.simplify() {
+                            ncater.notify_at(epoch);
+                        }
+                    }
+
                     let mut downstream_handle = downstream_output.activate();
                     let mut clock_handle = clock_output.activate();
+                    let mut should_halt = false;
+                    let frontier_epoch = input_frontiers.simplify();
+
                     ncater.for_each(
                         input_frontiers,
                         |caps, ()| {
@@ -276,38 +833,70 @@ where
                             let clock_cap = &caps[1];
                             let epoch = downstream_cap.time();
 
-                            if let Some(mut items) = items_inbuf.remove(epoch) {
-                                let mut downstream_session =
-                                    downstream_handle.session(downstream_cap);
+                            let items = items_inbuf.remove(epoch);
+                            if items.is_some() || heartbeat {
                                 let mut clock_session = clock_handle.session(clock_cap);
 
-                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
-                                    let inspector = inspector.bind(py);
-
-                                    for item in items.iter() {
-                                        let item = item.bind(py);
-
-                                        inspector
-                                            .call1((
-                                                step_id.clone(),
-                                                item,
-                                                epoch.clone(),
-                                                this_worker.0,
-                                            ))
-                                            .reraise_with(|| {
-                                                format!("error calling inspector in step {step_id}")
-                                            })?;
-                                    }
+                                if let Some(mut items) = items {
+                                    let mut downstream_session =
+                                        downstream_handle.session(downstream_cap);
 
-                                    Ok(())
-                                }));
+                                    unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
+                                        let inspector = inspector.bind(py);
+
+                                        for item in items.iter() {
+                                            let item = item.bind(py);
+
+                                            let call = if wants_frontier {
+                                                inspector.call1((
+                                                    step_id.clone(),
+                                                    item,
+                                                    epoch.clone(),
+                                                    this_worker.0,
+                                                    frontier_epoch.clone(),
+                                                ))
+                                            } else {
+                                                inspector.call1((
+                                                    step_id.clone(),
+                                                    item,
+                                                    epoch.clone(),
+                                                    this_worker.0,
+                                                ))
+                                            };
+
+                                            let halt = call
+                                                .reraise_with(|| {
+                                                    format!("error calling inspector in step {step_id}")
+                                                })?
+                                                .extract::<Option<bool>>()
+                                                .reraise_with(|| {
+                                                    format!(
+                                                        "return value of `inspector` in step \
+                                                        {step_id} must be `None` or a `bool`"
+                                                    )
+                                                })?
+                                                == Some(false);
+                                            should_halt |= halt;
+                                        }
+
+                                        Ok(())
+                                    }));
+
+                                    downstream_session.give_vec(&mut items);
+                                }
 
-                                downstream_session.give_vec(&mut items);
                                 clock_session.give(());
                             }
                         },
                         |_caps, ()| {},
                     );
+
+                    // Flushing the epoch above already happened; now
+                    // drop our capabilities so the dataflow winds down
+                    // cleanly instead of running the inspector forever.
+                    if should_halt {
+                        ncater.halt();
+                    }
                 });
             }
         });
@@ -316,6 +905,206 @@ where
     }
 }
 
+/// Cap on the number of distinct Python type names `profile` will
+/// track separately before lumping the rest into an `other` label, so
+/// a step that sees unbounded type variety (e.g. items carrying stack
+/// traces or other one-off object types) can't blow up the metric's
+/// label cardinality.
+const MAX_PROFILED_TYPES: usize = 32;
+
+pub(crate) trait ProfileOp<S>
+where
+    S: Scope,
+{
+    /// Pass items through unchanged while sampling their Python type
+    /// names into a `profile_item_type_count` metric, labeled by type
+    /// name.
+    ///
+    /// This reuses `inspect_debug`'s stateless pass-through structure,
+    /// but reports type-frequency counts instead of calling back into
+    /// Python for every item, so it's cheap enough to leave running in
+    /// production for data-quality monitoring.
+    fn profile(&self, step_id: StepId, sample_rate: f64) -> Stream<S, TdPyAny>;
+}
+
+impl<S> ProfileOp<S> for Stream<S, TdPyAny>
+where
+    S: Scope,
+{
+    fn profile(&self, step_id: StepId, sample_rate: f64) -> Stream<S, TdPyAny> {
+        let this_worker = self.scope().w_index();
+
+        let mut op_builder = OperatorBuilder::new(step_id.0.clone(), self.scope());
+        let mut self_handle = op_builder.new_input(self, Pipeline);
+        let (mut downstream_output, downstream) = op_builder.new_output();
+
+        let meter = opentelemetry::global::meter("bytewax");
+        let type_count = meter
+            .u64_counter("profile_item_type_count")
+            .with_description(
+                "count of sampled items seen at this `profile` step, \
+                labeled by Python type name; capped to a fixed number \
+                of distinct type names, with the rest reported under \
+                `other`",
+            )
+            .init();
+        let labels = vec![
+            KeyValue::new("step_id", step_id.0.to_string()),
+            KeyValue::new("worker_index", this_worker.0.to_string()),
+        ];
+
+        op_builder.build(move |_init_caps| {
+            let mut inbuf = Vec::new();
+            let mut seen_types: BTreeSet<String> = BTreeSet::new();
+
+            move |_frontiers| {
+                let mut downstream_handle = downstream_output.activate();
+
+                self_handle.for_each(|time, data| {
+                    data.swap(&mut inbuf);
+                    let mut downstream_session = downstream_handle.session(&time);
+
+                    Python::with_gil(|py| {
+                        for item in inbuf.iter() {
+                            if fastrand::f64() >= sample_rate {
+                                continue;
+                            }
+
+                            let type_name =
+                                unwrap_any!(item.bind(py).get_type().name()).to_string();
+                            let type_label = if seen_types.contains(&type_name) {
+                                type_name
+                            } else if seen_types.len() < MAX_PROFILED_TYPES {
+                                seen_types.insert(type_name.clone());
+                                type_name
+                            } else {
+                                "other".to_string()
+                            };
+
+                            let mut item_labels = labels.clone();
+                            item_labels.push(KeyValue::new("type", type_label));
+                            type_count.add(1, &item_labels);
+                        }
+                    });
+
+                    downstream_session.give_vec(&mut inbuf);
+                });
+            }
+        });
+
+        downstream
+    }
+}
+
+pub(crate) trait SampleStreamOp<S>
+where
+    S: Scope,
+{
+    /// Randomly drop items so only a `fraction` of the stream passes
+    /// through.
+    ///
+    /// Unlike [`ProfileOp::profile`]'s `sample_rate`, which only
+    /// affects what's reported to metrics, this actually drops items
+    /// from the stream, reducing downstream load. Useful for replaying
+    /// a production stream at reduced volume for capacity planning.
+    fn sample_stream(&self, step_id: StepId, fraction: f64, seed: Option<u64>) -> Stream<S, TdPyAny>;
+}
+
+impl<S> SampleStreamOp<S> for Stream<S, TdPyAny>
+where
+    S: Scope,
+{
+    fn sample_stream(&self, step_id: StepId, fraction: f64, seed: Option<u64>) -> Stream<S, TdPyAny> {
+        let mut op_builder = OperatorBuilder::new(step_id.0.clone(), self.scope());
+        let mut self_handle = op_builder.new_input(self, Pipeline);
+        let (mut downstream_output, downstream) = op_builder.new_output();
+
+        op_builder.build(move |_init_caps| {
+            let mut inbuf = Vec::new();
+            // A per-operator, seeded `Rng` when `seed` is given, so
+            // sampling is deterministic run to run for reproducible
+            // load tests; otherwise fall back to the same
+            // thread-local `fastrand` source used elsewhere in this
+            // file (e.g. `ProfileOp::profile`).
+            let rng = seed.map(fastrand::Rng::with_seed);
+
+            move |_frontiers| {
+                let mut downstream_handle = downstream_output.activate();
+
+                self_handle.for_each(|time, data| {
+                    data.swap(&mut inbuf);
+                    let mut downstream_session = downstream_handle.session(&time);
+
+                    for item in inbuf.drain(..) {
+                        let sampled = match &rng {
+                            Some(rng) => rng.f64(),
+                            None => fastrand::f64(),
+                        };
+                        if sampled < fraction {
+                            downstream_session.give(item);
+                        }
+                    }
+                });
+            }
+        });
+
+        downstream
+    }
+}
+
+pub(crate) trait TimestampOp<S>
+where
+    S: Scope,
+{
+    /// Wrap each item as `(item, ingest_time)`, where `ingest_time` is
+    /// a Python `datetime` of the wall-clock moment this operator saw
+    /// the item.
+    ///
+    /// Distinct from the epoch: the epoch is a dataflow-internal
+    /// progress marker, while `ingest_time` is real wall-clock time,
+    /// for measuring end-to-end processing latency against an SLO.
+    /// A dedicated operator rather than folding this into `map` so
+    /// items aren't forcibly wrapped at every step that might want a
+    /// timestamp.
+    fn timestamp(&self, step_id: StepId) -> Stream<S, TdPyAny>;
+}
+
+impl<S> TimestampOp<S> for Stream<S, TdPyAny>
+where
+    S: Scope,
+{
+    fn timestamp(&self, step_id: StepId) -> Stream<S, TdPyAny> {
+        let mut op_builder = OperatorBuilder::new(step_id.0.clone(), self.scope());
+        let mut self_handle = op_builder.new_input(self, Pipeline);
+        let (mut downstream_output, downstream) = op_builder.new_output();
+
+        op_builder.build(move |_init_caps| {
+            let mut inbuf = Vec::new();
+
+            move |_frontiers| {
+                let mut downstream_handle = downstream_output.activate();
+
+                self_handle.for_each(|time, data| {
+                    data.swap(&mut inbuf);
+                    let mut downstream_session = downstream_handle.session(&time);
+
+                    Python::with_gil(|py| {
+                        for item in inbuf.drain(..) {
+                            let ingest_time = chrono::offset::Utc::now();
+                            let item: PyObject = item.into();
+                            let wrapped =
+                                IntoPy::<PyObject>::into_py((item, ingest_time), py);
+                            downstream_session.give(TdPyAny::from(wrapped));
+                        }
+                    });
+                });
+            }
+        });
+
+        downstream
+    }
+}
+
 pub(crate) trait MergeOp<S>
 where
     S: Scope,
@@ -338,16 +1127,176 @@ where
         _step_id: StepId,
         ups: Vec<Stream<S, TdPyAny>>,
     ) -> PyResult<Stream<S, TdPyAny>> {
+        // A failure in any of `ups` panics the worker thread rather
+        // than flowing through the dataflow as a value, so there's
+        // no per-upstream error to catch here; a healthy upstream
+        // can't be kept alive once another one fails. `merge_isolated`
+        // covers the one case narrow enough to actually fix.
         Ok(self.concatenate(ups))
     }
 }
 
+pub(crate) trait MergeIsolatedOp<S>
+where
+    S: Scope,
+{
+    /// Like [`MergeOp::merge`], but isolates a failure in `mapper`,
+    /// called on every item from every upstream before it's passed
+    /// downstream, to just the upstream that produced the offending
+    /// item, instead of letting it take down the worker thread. This
+    /// can't help with a failure raised by some *other* operator
+    /// upstream of this one, since that still unwinds the worker
+    /// thread before the item ever reaches here; `mapper` is the
+    /// only user code this operator itself runs, so it's the only
+    /// failure it can actually isolate.
+    ///
+    /// The second output carries the index (into `ups`) of any
+    /// upstream whose item tripped this; that upstream is dropped
+    /// from the first output from then on.
+    fn merge_isolated(
+        &self,
+        py: Python,
+        step_id: StepId,
+        mapper: TdPyCallable,
+        ups: Vec<Stream<S, TdPyAny>>,
+    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>)>;
+}
+
+impl<S> MergeIsolatedOp<S> for S
+where
+    S: Scope,
+{
+    fn merge_isolated(
+        &self,
+        _py: Python,
+        step_id: StepId,
+        mapper: TdPyCallable,
+        ups: Vec<Stream<S, TdPyAny>>,
+    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>)> {
+        let scope = ups
+            .first()
+            .map(|up| up.scope())
+            .unwrap_or_else(|| self.clone());
+        let mut op_builder = OperatorBuilder::new(format!("{step_id}.merge_isolated"), scope);
+
+        let mut up_handles: Vec<_> = ups
+            .iter()
+            .map(|up| op_builder.new_input(up, Pipeline))
+            .collect();
+        let (mut down_output, down) = op_builder.new_output();
+        let (mut errors_output, errors) = op_builder.new_output();
+
+        op_builder.build(move |_| {
+            let mut inbuf = Vec::new();
+            let mut closed = vec![false; up_handles.len()];
+            move |_frontiers| {
+                let mut down_handle = down_output.activate();
+                let mut errors_handle = errors_output.activate();
+
+                Python::with_gil(|py| {
+                    let mapper = mapper.bind(py);
+                    for (source_index, up_handle) in up_handles.iter_mut().enumerate() {
+                        up_handle.for_each(|time, data| {
+                            if closed[source_index] {
+                                return;
+                            }
+                            data.swap(&mut inbuf);
+                            let mut down_session = down_handle.session(&time);
+                            for item in inbuf.drain(..) {
+                                let res =
+                                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                                        mapper
+                                            .call1((item.bind(py),))
+                                            .map(|obj| TdPyAny::from(obj.unbind()))
+                                    }));
+                                let failure = match res {
+                                    Ok(Ok(mapped)) => {
+                                        down_session.give(mapped);
+                                        None
+                                    }
+                                    Ok(Err(err)) => Some(format!("error calling `mapper`: {err}")),
+                                    Err(payload) => Some(format!(
+                                        "panic calling `mapper`: {}",
+                                        panic_msg(&*payload)
+                                    )),
+                                };
+                                if let Some(reason) = failure {
+                                    tracing::warn!(
+                                        "upstream {source_index} of `merge_isolated` \
+                                        in step {step_id} closed: {reason}"
+                                    );
+                                    closed[source_index] = true;
+                                    let marker = TdPyAny::from(source_index.into_py(py));
+                                    errors_handle.session(&time).give(marker);
+                                    break;
+                                }
+                            }
+                        });
+                    }
+                });
+            }
+        });
+
+        Ok((down, errors))
+    }
+}
+
+pub(crate) trait MergeTaggedOp<S>
+where
+    S: Scope,
+{
+    fn merge_tagged(
+        &self,
+        py: Python,
+        step_id: StepId,
+        ups: Vec<Stream<S, TdPyAny>>,
+    ) -> PyResult<Stream<S, TdPyAny>>;
+}
+
+impl<S> MergeTaggedOp<S> for S
+where
+    S: Scope,
+{
+    fn merge_tagged(
+        &self,
+        _py: Python,
+        _step_id: StepId,
+        ups: Vec<Stream<S, TdPyAny>>,
+    ) -> PyResult<Stream<S, TdPyAny>> {
+        // Tag each upstream with its position before merging, so a
+        // failure in any of `ups` still just panics the worker
+        // thread rather than flowing through as a value; see `merge`.
+        let tagged: Vec<_> = ups
+            .into_iter()
+            .enumerate()
+            .map(|(source_index, up)| {
+                up.map(move |item| {
+                    let item = PyObject::from(item);
+                    let tagged_item = Python::with_gil(|py| {
+                        IntoPy::<PyObject>::into_py((source_index, item), py)
+                    });
+                    TdPyAny::from(tagged_item)
+                })
+            })
+            .collect();
+        Ok(self.concatenate(tagged))
+    }
+}
+
 pub(crate) trait RedistributeOp<S, D>
 where
     S: Scope,
     D: ExchangeData,
 {
     fn redistribute(&self, step_id: StepId) -> Stream<S, D>;
+
+    /// Like [`redistribute`], but only exchanges items among
+    /// `workers`, instead of every worker in the cluster. Useful for
+    /// pinning a step to a subset of workers, e.g. ones with a GPU.
+    ///
+    /// Errors if `workers` is empty or contains an index `>=` the
+    /// actual worker count.
+    fn redistribute_to(&self, step_id: StepId, workers: Vec<usize>) -> PyResult<Stream<S, D>>;
 }
 
 impl<S, D> RedistributeOp<S, D> for Stream<S, D>
@@ -358,8 +1307,78 @@ where
     fn redistribute(&self, _step_id: StepId) -> Stream<S, D> {
         self.exchange(move |_| fastrand::u64(..))
     }
+
+    fn redistribute_to(&self, step_id: StepId, workers: Vec<usize>) -> PyResult<Stream<S, D>> {
+        let worker_count = self.scope().w_count();
+
+        if workers.is_empty() {
+            return Err(tracked_err::<PyValueError>(&format!(
+                "`redistribute_to` in step {step_id} was given an empty `workers` list"
+            )));
+        }
+        if let Some(&bad) = workers.iter().find(|&&w| w >= worker_count.0) {
+            return Err(tracked_err::<PyValueError>(&format!(
+                "`redistribute_to` in step {step_id} was given out-of-range worker index \
+                {bad}; only {} workers are running",
+                worker_count.0
+            )));
+        }
+
+        Ok(self.exchange(move |_| workers[fastrand::usize(..workers.len())] as u64))
+    }
+}
+
+pub(crate) trait BroadcastOp<S, D>
+where
+    S: Scope,
+    D: ExchangeData,
+{
+    /// Send every item to every worker.
+    ///
+    /// This multiplies the volume of the stream by the worker count,
+    /// so this is only appropriate for low-volume control data, e.g.
+    /// broadcasting a small piece of configuration to join against a
+    /// stream that's sharded across the cluster.
+    fn broadcast(&self, step_id: StepId) -> Stream<S, D>;
+}
+
+impl<S, D> BroadcastOp<S, D> for Stream<S, D>
+where
+    S: Scope,
+    D: ExchangeData,
+{
+    fn broadcast(&self, _step_id: StepId) -> Stream<S, D> {
+        Broadcast::broadcast(self)
+    }
+}
+
+/// Describe a caught panic payload for an error message.
+fn panic_msg(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+/// Truncate a `repr()` so an oversized item doesn't blow up an error
+/// message.
+fn truncated_repr(repr: &str) -> String {
+    const MAX_CHARS: usize = 200;
+    match repr.char_indices().nth(MAX_CHARS) {
+        Some((byte_idx, _)) => format!("{}...", &repr[..byte_idx]),
+        None => repr.to_string(),
+    }
 }
 
+/// Low-level routing helper used to build stateful operators; it
+/// converts the Python-level key into a typed [`StateKey`] so the
+/// dataflow can partition on it. Transforming just the value of a
+/// keyed stream from user code should go through
+/// `bytewax.operators.map_value`, which stays at the Python `(key,
+/// value)` tuple level and doesn't need this conversion at all.
 pub(crate) trait ExtractKeyOp<S>
 where
     S: Scope,
@@ -386,24 +1405,107 @@ where
                 Python::with_gil(|py| {
                     self_handle.for_each(|time, data| {
                         data.swap(&mut inbuf);
+                        let epoch = time.time();
                         let mut downstream_session = downstream_handle.session(&time);
                         unwrap_any!(|| -> PyResult<()> {
-                            for item in inbuf.drain(..) {
+                            for (idx, item) in inbuf.drain(..).enumerate() {
                                 let item = PyObject::from(item);
                                 let (key, value) = item
                                     .extract::<(&PyAny, PyObject)>(py)
                                     .raise_with::<PyTypeError>(|| {
-                                        format!("step {for_step_id} requires `(key, value)` 2-tuple from upstream for routing; got a `{}` instead",
-                                            unwrap_any!(item.bind(py).get_type().name()),
-                                        )
+                                        let preview = truncated_repr(unwrap_any!(unwrap_any!(item.bind(py).repr()).to_str()));
+                                        format!("step {for_step_id} requires `(key, value)` 2-tuple from upstream for routing; got a `{}` instead (item {idx} of epoch {epoch:?}: {preview})",
+                                            unwrap_any!(item.bind(py).get_type().name()),
+                                        )
+                                    })?;
+
+                                let key = key.extract::<StateKey>().raise_with::<PyTypeError>(|| {
+                                    let preview = truncated_repr(unwrap_any!(unwrap_any!(key.repr()).to_str()));
+                                    format!("step {for_step_id} requires `str` keys in `(key, value)` from upstream; got a `{}` instead (item {idx} of epoch {epoch:?}: {preview})",
+                                        unwrap_any!(key.get_type().name()),
+                                    )
+                                })?;
+                                downstream_session.give((key, TdPyAny::from(value)));
+                            }
+                            Ok(())
+                        }());
+                    });
+                });
+            }
+        });
+        downstream
+    }
+}
+
+pub(crate) trait WrapKeyOp<S>
+where
+    S: Scope,
+{
+    fn wrap_key(&self) -> Stream<S, TdPyAny>;
+}
+
+impl<S> WrapKeyOp<S> for Stream<S, (StateKey, TdPyAny)>
+where
+    S: Scope,
+{
+    fn wrap_key(&self) -> Stream<S, TdPyAny> {
+        self.map(move |(key, value)| {
+            let value = PyObject::from(value);
+
+            let item = Python::with_gil(|py| IntoPy::<PyObject>::into_py((key, value), py));
+
+            TdPyAny::from(item)
+        })
+    }
+}
+
+pub(crate) trait RekeyOp<S>
+where
+    S: Scope,
+{
+    /// Recompute the key of a keyed stream from its value and
+    /// re-shuffle to the worker that now owns the new key.
+    ///
+    /// Unlike unwrapping to `(key, value)`, building a new tuple in
+    /// Python, and re-extracting via `extract_key`, this computes the
+    /// new key and re-keys the item in one step, without a Python
+    /// round trip to reconstruct the tuple.
+    fn rekey(&self, step_id: StepId, key_fn: TdPyCallable) -> Stream<S, (StateKey, TdPyAny)>;
+}
+
+impl<S> RekeyOp<S> for Stream<S, (StateKey, TdPyAny)>
+where
+    S: Scope,
+{
+    fn rekey(&self, step_id: StepId, key_fn: TdPyCallable) -> Stream<S, (StateKey, TdPyAny)> {
+        let mut op_builder = OperatorBuilder::new(format!("{step_id}.rekey"), self.scope());
+        let mut self_handle = op_builder.new_input(self, Pipeline);
+
+        let (mut downstream_output, downstream) = op_builder.new_output();
+
+        op_builder.build(move |_| {
+            let mut inbuf = Vec::new();
+            move |_frontiers| {
+                let mut downstream_handle = downstream_output.activate();
+
+                Python::with_gil(|py| {
+                    self_handle.for_each(|time, data| {
+                        data.swap(&mut inbuf);
+                        let mut downstream_session = downstream_handle.session(&time);
+                        unwrap_any!(|| -> PyResult<()> {
+                            for (_old_key, value) in inbuf.drain(..) {
+                                let value = PyObject::from(value);
+                                let new_key = key_fn
+                                    .bind(py)
+                                    .call1((value.clone_ref(py),))
+                                    .reraise_with(|| {
+                                        format!("error calling `key_fn` in step {step_id}")
+                                    })?
+                                    .extract::<StateKey>()
+                                    .raise_with::<PyTypeError>(|| {
+                                        format!("`key_fn` in step {step_id} must return a `str`")
                                     })?;
-
-                                let key = key.extract::<StateKey>().raise_with::<PyTypeError>(|| {
-                                    format!("step {for_step_id} requires `str` keys in `(key, value)` from upstream; got a `{}` instead",
-                                        unwrap_any!(key.get_type().name()),
-                                    )
-                                })?;
-                                downstream_session.give((key, TdPyAny::from(value)));
+                                downstream_session.give((new_key, TdPyAny::from(value)));
                             }
                             Ok(())
                         }());
@@ -411,29 +1513,184 @@ where
                 });
             }
         });
-        downstream
+
+        // Re-shuffle using the same hash `stateful` operators use
+        // internally, so a rekeyed stream that feeds directly into
+        // one doesn't pay for a second, redundant exchange.
+        let pf = BuildHasherDefault::<SeaHasher>::default();
+        downstream.exchange(move |(key, _value)| pf.assign(key) as u64)
     }
 }
 
-pub(crate) trait WrapKeyOp<S>
+/// Number of registers `key_cardinality`'s [`HyperLogLog`] estimator
+/// uses when `approx` is set, as a power of two. 14 bits (16,384
+/// registers of 6 bits each, ~12 KB per step per worker) gets a
+/// standard error of about 1%, regardless of how many distinct keys
+/// are actually seen.
+const KEY_CARDINALITY_HLL_PRECISION: u32 = 14;
+
+/// A small HyperLogLog cardinality estimator.
+///
+/// Uses the classic Flajolet et al. estimator with linear-counting
+/// correction in the low-cardinality range; no bias-correction lookup
+/// tables, which is plenty accurate for a per-epoch metric. Hashes
+/// with `seahash`, already a dependency for `stateful`'s key routing,
+/// instead of pulling in a dedicated cardinality-estimation crate.
+struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    fn new() -> Self {
+        Self {
+            registers: vec![0; 1 << KEY_CARDINALITY_HLL_PRECISION],
+        }
+    }
+
+    fn insert(&mut self, key: &StateKey) {
+        let hash = seahash::hash(key.0.as_bytes());
+        let index = (hash & ((1 << KEY_CARDINALITY_HLL_PRECISION) - 1)) as usize;
+        let rest = hash >> KEY_CARDINALITY_HLL_PRECISION;
+        // `+ 1` so an all-zero `rest` counts as a run of one leading
+        // zero, not zero.
+        let rank = (rest.trailing_zeros() + 1).min(64 - KEY_CARDINALITY_HLL_PRECISION) as u8;
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank);
+    }
+
+    fn len(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+        let raw = alpha * m * m / sum;
+
+        let estimate = if raw <= 2.5 * m {
+            let zero_registers = self.registers.iter().filter(|&&r| r == 0).count();
+            if zero_registers > 0 {
+                m * (m / zero_registers as f64).ln()
+            } else {
+                raw
+            }
+        } else {
+            raw
+        };
+
+        estimate.round() as u64
+    }
+
+    fn clear(&mut self) {
+        self.registers.iter_mut().for_each(|r| *r = 0);
+    }
+}
+
+/// Tracks the distinct keys seen this epoch for `key_cardinality`,
+/// either exactly or via a [`HyperLogLog`] approximation to bound
+/// memory for huge key spaces.
+enum KeyCardinalityCounter {
+    Exact(HashSet<StateKey>),
+    Approx(HyperLogLog),
+}
+
+impl KeyCardinalityCounter {
+    fn new(approx: bool) -> Self {
+        if approx {
+            Self::Approx(HyperLogLog::new())
+        } else {
+            Self::Exact(HashSet::new())
+        }
+    }
+
+    fn insert(&mut self, key: &StateKey) {
+        match self {
+            Self::Exact(seen) => {
+                seen.insert(key.clone());
+            }
+            Self::Approx(hll) => hll.insert(key),
+        }
+    }
+
+    /// Return the count of distinct keys seen since the last call,
+    /// then reset for the next epoch.
+    fn count_and_reset(&mut self) -> u64 {
+        match self {
+            Self::Exact(seen) => {
+                let count = seen.len() as u64;
+                seen.clear();
+                count
+            }
+            Self::Approx(hll) => {
+                let count = hll.len();
+                hll.clear();
+                count
+            }
+        }
+    }
+}
+
+pub(crate) trait KeyCardinalityOp<S>
 where
-    S: Scope,
+    S: Scope<Timestamp = u64>,
 {
-    fn wrap_key(&self) -> Stream<S, TdPyAny>;
+    /// Count the number of distinct keys seen per epoch on a keyed
+    /// stream, emitting `(epoch, count)` once that epoch closes.
+    ///
+    /// Re-shuffles using the same hash `stateful` operators use
+    /// internally, so a key that's been split across workers by an
+    /// upstream step is only ever counted on one of them.
+    fn key_cardinality(&self, step_id: StepId, approx: bool) -> Stream<S, TdPyAny>;
 }
 
-impl<S> WrapKeyOp<S> for Stream<S, (StateKey, TdPyAny)>
+impl<S> KeyCardinalityOp<S> for Stream<S, (StateKey, TdPyAny)>
 where
-    S: Scope,
+    S: Scope<Timestamp = u64>,
 {
-    fn wrap_key(&self) -> Stream<S, TdPyAny> {
-        self.map(move |(key, value)| {
-            let value = PyObject::from(value);
+    fn key_cardinality(&self, step_id: StepId, approx: bool) -> Stream<S, TdPyAny> {
+        let pf = BuildHasherDefault::<SeaHasher>::default();
+        let routed = self.exchange(move |(key, _value)| pf.assign(key) as u64);
 
-            let item = Python::with_gil(|py| IntoPy::<PyObject>::into_py((key, value), py));
+        let mut op_builder = OperatorBuilder::new(step_id.0.clone(), routed.scope());
+        let mut self_handle = op_builder.new_input(&routed, Pipeline);
+        let (mut downstream_output, downstream) = op_builder.new_output();
 
-            TdPyAny::from(item)
-        })
+        op_builder.build(move |init_caps| {
+            let mut items_inbuf = InBuffer::new();
+            let mut ncater = EagerNotificator::new(init_caps, KeyCardinalityCounter::new(approx));
+
+            move |input_frontiers| {
+                tracing::debug_span!("operator", operator = step_id.0.clone()).in_scope(|| {
+                    self_handle.buffer_notify(&mut items_inbuf, &mut ncater);
+
+                    let mut downstream_handle = downstream_output.activate();
+
+                    ncater.for_each(
+                        input_frontiers,
+                        |caps, counter| {
+                            let cap = &caps[0];
+                            let epoch = cap.time();
+
+                            if let Some(items) = items_inbuf.remove(epoch) {
+                                for (key, _value) in items {
+                                    counter.insert(&key);
+                                }
+                            }
+                        },
+                        |caps, counter| {
+                            let cap = &caps[0];
+                            let epoch = *cap.time();
+
+                            let count = counter.count_and_reset();
+                            let mut downstream_session = downstream_handle.session(cap);
+                            let item = Python::with_gil(|py| {
+                                TdPyAny::from(IntoPy::<PyObject>::into_py((epoch, count), py))
+                            });
+                            downstream_session.give(item);
+                        },
+                    );
+                });
+            }
+        });
+
+        downstream
     }
 }
 
@@ -441,6 +1698,65 @@ pub(crate) trait StatefulBatchOp<S>
 where
     S: Scope<Timestamp = u64>,
 {
+    /// If `emit_discards` is `True`, an extra `(key, epoch)` is given
+    /// on the returned discards stream every time a key transitions
+    /// to [`IsComplete::Discard`]. Left `False`, that stream is never
+    /// written to, so dataflows that don't need it don't pay for it.
+    ///
+    /// `partition_fn`, if given, is called with a state key and must
+    /// return the index of the worker that should own that key's
+    /// state, overriding the default hash-based routing. Defaults to
+    /// `None`, using the same hash as other operators.
+    ///
+    /// `workers` is a stream enumerating every worker in the
+    /// cluster, used to route state to its owning worker. Build it
+    /// once per scope with [`workers_stream`] and share it across
+    /// every stateful step, rather than having each step build its
+    /// own.
+    ///
+    /// `notify_coalesce_interval`, if given, rounds up the delay
+    /// before the next `notify_at`-driven activation to the nearest
+    /// multiple of this duration, so keys with due times within the
+    /// same window wake the operator once instead of each causing
+    /// their own activation. Defaults to `None`, meaning no
+    /// coalescing, which matches today's behavior of activating
+    /// exactly at the soonest due time.
+    ///
+    /// `snapshot_interval`, if given, only snapshots a key's state
+    /// once every this many closed epochs, rather than every one.
+    /// Discards are never delayed by this, since they're rare and
+    /// leaving one unwritten would keep an obsolete state around on
+    /// disk. Defaults to `None`, snapshotting every closed epoch.
+    ///
+    /// `partition_seed`, if given and `partition_fn` is `None`, is
+    /// mixed into the default hash used for self-partitioning, so
+    /// placement can be deliberately reshuffled (e.g. to fix skew)
+    /// without writing a custom `partition_fn`. Changing it
+    /// invalidates the placement recorded in any existing resume
+    /// snapshot, so it must be paired with the rescale/repartition
+    /// recovery path. Defaults to `None`, using today's unseeded
+    /// hash, so existing snapshots' placement is unaffected.
+    ///
+    /// `ctrl` is a stream of checkpoint requests. Any item received on
+    /// it during an epoch immediately snapshots every key this worker
+    /// has awoken so far in that epoch, without waiting for the epoch
+    /// to close or for `snapshot_interval` to come due. This is purely
+    /// additive to the normal on-close snapshotting, so it's always
+    /// safe to trigger, just wasteful if overused.
+    ///
+    /// `resume_lazily`, if `True`, defers calling `builder` for a
+    /// resumed key until it's first touched by a live item instead of
+    /// doing so for every key up front, trading away eager
+    /// `notify_at` scheduling for idle resumed keys (they won't wake
+    /// up on their own until touched) for lower resume-time latency
+    /// on steps with a huge key space. Every resumed key's state is
+    /// still read from the recovery store up front either way (see
+    /// `pending_resume` below), so this doesn't lower resume-time
+    /// memory use, only defers the `builder` call itself. Defaults to
+    /// `False`, which matches today's eager behavior and is the right
+    /// choice for logic that relies on `notify_at` firing without a
+    /// new item arriving first (e.g. timeouts, windowing).
+    #[allow(clippy::too_many_arguments)]
     fn stateful_batch(
         &self,
         py: Python,
@@ -448,7 +1764,30 @@ where
         builder: TdPyCallable,
         resume_epoch: ResumeEpoch,
         loads: &Stream<S, Snapshot>,
-    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, Snapshot>)>;
+        workers: &Stream<S, WorkerIndex>,
+        ctrl: &Stream<S, TdPyAny>,
+        emit_discards: bool,
+        partition_fn: Option<TdPyCallable>,
+        partition_seed: Option<u64>,
+        notify_coalesce_interval: Option<TimeDelta>,
+        snapshot_interval: Option<u64>,
+        resume_lazily: bool,
+    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>, Stream<S, Snapshot>)>;
+}
+
+/// Build a stream enumerating every worker in the cluster, once per
+/// scope.
+///
+/// Several stateful operators need this to route state to its
+/// owning worker. Building it once here and sharing the resulting
+/// stream, instead of each operator building and discarding its own
+/// copy, keeps dataflows with many stateful steps smaller and
+/// cheaper to build.
+pub(crate) fn workers_stream<S>(scope: &mut S) -> Stream<S, WorkerIndex>
+where
+    S: Scope<Timestamp = u64>,
+{
+    scope.w_count().iter().to_stream(scope)
 }
 
 struct StatefulBatchLogic(PyObject);
@@ -509,16 +1848,87 @@ impl StatefulBatchLogic {
         Ok((emit, is_complete))
     }
 
+    /// Like [`Self::extract_ret`], but `on_batch` may additionally
+    /// return a third list of the values it didn't get a chance to
+    /// consume, to be re-delivered to it (along with any new values
+    /// for the same key) on the next activation. Omitting the third
+    /// element keeps today's behavior of the whole batch being
+    /// consumed.
+    ///
+    /// It may also return a fourth list of donor key strings whose
+    /// state should be merged into this key's, see
+    /// [`Self::extract_batch_ret`]'s caller in `stateful_batch` for
+    /// how that's carried out. Omitting the fourth element means no
+    /// merge is requested.
+    fn extract_batch_ret(
+        res: Bound<'_, PyAny>,
+    ) -> PyResult<(Vec<PyObject>, IsComplete, Vec<PyObject>, Vec<String>)> {
+        if let Ok((emit, is_complete, retry, merge_from)) =
+            res.extract::<(&PyAny, &PyAny, &PyAny, &PyAny)>()
+        {
+            let is_complete = is_complete.extract::<IsComplete>()?;
+            let emit = emit.extract::<Vec<_>>().reraise_with(|| {
+                format!(
+                    "`emit` was not a `list`; got a `{}` instead",
+                    unwrap_any!(emit.get_type().name())
+                )
+            })?;
+            let retry = retry.extract::<Vec<_>>().reraise_with(|| {
+                format!(
+                    "`retry` was not a `list`; got a `{}` instead",
+                    unwrap_any!(retry.get_type().name())
+                )
+            })?;
+            let merge_from = merge_from.extract::<Vec<String>>().reraise_with(|| {
+                format!(
+                    "`merge_from` was not a `list` of `str`; got a `{}` instead",
+                    unwrap_any!(merge_from.get_type().name())
+                )
+            })?;
+            Ok((emit, is_complete, retry, merge_from))
+        } else if let Ok((emit, is_complete, retry)) = res.extract::<(&PyAny, &PyAny, &PyAny)>() {
+            let is_complete = is_complete.extract::<IsComplete>()?;
+            let emit = emit.extract::<Vec<_>>().reraise_with(|| {
+                format!(
+                    "`emit` was not a `list`; got a `{}` instead",
+                    unwrap_any!(emit.get_type().name())
+                )
+            })?;
+            let retry = retry.extract::<Vec<_>>().reraise_with(|| {
+                format!(
+                    "`retry` was not a `list`; got a `{}` instead",
+                    unwrap_any!(retry.get_type().name())
+                )
+            })?;
+            Ok((emit, is_complete, retry, Vec::new()))
+        } else {
+            let (emit, is_complete) = Self::extract_ret(res)?;
+            Ok((emit, is_complete, Vec::new(), Vec::new()))
+        }
+    }
+
     fn on_batch<'py>(
         &'py self,
         py: Python<'py>,
         items: Vec<PyObject>,
-    ) -> PyResult<(Vec<PyObject>, IsComplete)> {
+    ) -> PyResult<(Vec<PyObject>, IsComplete, Vec<PyObject>, Vec<String>)> {
         let res = self
             .0
             .bind(py)
             .call_method1(intern!(py, "on_batch"), (items,))?;
-        Self::extract_ret(res).reraise("error extracting `(emit, is_complete)`")
+        Self::extract_batch_ret(res).reraise(
+            "error extracting `(emit, is_complete)`, `(emit, is_complete, retry)`, or \
+            `(emit, is_complete, retry, merge_from)`",
+        )
+    }
+
+    /// Incorporate a donor key's snapshotted state into this logic,
+    /// on behalf of an `on_batch` that returned a `merge_from` list.
+    fn merge<'py>(&'py self, py: Python<'py>, donor_state: PyObject) -> PyResult<()> {
+        self.0
+            .bind(py)
+            .call_method1(intern!(py, "merge"), (donor_state,))?;
+        Ok(())
     }
 
     fn on_notify<'py>(&'py self, py: Python<'py>) -> PyResult<(Vec<PyObject>, IsComplete)> {
@@ -526,11 +1936,43 @@ impl StatefulBatchLogic {
         Self::extract_ret(res).reraise("error extracting `(emit, is_complete)`")
     }
 
+    /// Calls `on_notify_batch` on behalf of every key due this
+    /// activation in a single Python call, rather than one call per
+    /// key. `self` only serves as the bound method's receiver;
+    /// `on_notify_batch` is a `classmethod`, so its default
+    /// implementation ignores it and just calls each `logic`'s
+    /// `on_notify` in turn.
+    fn on_notify_batch<'py>(
+        &'py self,
+        py: Python<'py>,
+        due: Vec<(String, PyObject)>,
+    ) -> PyResult<HashMap<String, (Vec<PyObject>, IsComplete)>> {
+        let res = self
+            .0
+            .bind(py)
+            .call_method1(intern!(py, "on_notify_batch"), (due,))?;
+        res.extract().reraise(
+            "error extracting `on_notify_batch` return value; expected a `dict` mapping key to `(emit, is_complete)`",
+        )
+    }
+
     fn on_eof<'py>(&'py self, py: Python<'py>) -> PyResult<(Vec<PyObject>, IsComplete)> {
         let res = self.0.bind(py).call_method0("on_eof")?;
         Self::extract_ret(res).reraise("error extracting `(emit, is_complete)`")
     }
 
+    fn on_epoch_close<'py>(
+        &'py self,
+        py: Python<'py>,
+        epoch: PyObject,
+    ) -> PyResult<(Vec<PyObject>, IsComplete)> {
+        let res = self
+            .0
+            .bind(py)
+            .call_method1(intern!(py, "on_epoch_close"), (epoch,))?;
+        Self::extract_ret(res).reraise("error extracting `(emit, is_complete)`")
+    }
+
     fn notify_at(&self, py: Python) -> PyResult<Option<DateTime<Utc>>> {
         let res = self.0.bind(py).call_method0(intern!(py, "notify_at"))?;
         res.extract().reraise_with(|| {
@@ -541,15 +1983,136 @@ impl StatefulBatchLogic {
         })
     }
 
+    fn should_snapshot(&self, py: Python) -> PyResult<bool> {
+        let res = self
+            .0
+            .bind(py)
+            .call_method0(intern!(py, "should_snapshot"))?;
+        res.extract().reraise_with(|| {
+            format!(
+                "`should_snapshot` did not return a `bool`; got a `{}` instead",
+                unwrap_any!(res.get_type().name())
+            )
+        })
+    }
+
     fn snapshot(&self, py: Python) -> PyResult<PyObject> {
         self.0.call_method0(py, intern!(py, "snapshot"))
     }
 }
 
+/// How many keys `stateful_batch` should resume between each
+/// progress log line while loading snapshots, or `None` to only log
+/// once loading finishes.
+///
+/// Resume snapshots are streamed in and applied to `logics` all
+/// before any live item is processed (see the `loads_inbuf` handling
+/// in `stateful_batch`'s activation closure), so a step with millions
+/// of keys can otherwise look hung for a long time with no signal
+/// other than the final `"resumed from ... with N keys"` summary. Set
+/// `BYTEWAX_STATEFUL_BATCH_RESUME_LOG_INTERVAL` to an integer to get a
+/// line every that many keys instead. Purely an observability knob;
+/// see `resume_lazily` on `stateful_batch` for something that
+/// actually shrinks resume-time latency instead of just
+/// making the wait visible.
+fn resume_progress_log_interval() -> Option<u64> {
+    static INTERVAL: std::sync::OnceLock<Option<u64>> = std::sync::OnceLock::new();
+    *INTERVAL.get_or_init(|| {
+        std::env::var("BYTEWAX_STATEFUL_BATCH_RESUME_LOG_INTERVAL")
+            .ok()
+            .and_then(|v| v.parse().ok())
+    })
+}
+
+/// Cap on how many items of a single key `stateful_batch` will hand to
+/// one `on_batch` call per activation, or `None` for today's behavior
+/// of always handing over the whole batch.
+///
+/// Without a cap, one enormous key's `on_batch` call can consume a
+/// whole activation, delaying `on_notify`/`on_eof` and other keys'
+/// `on_batch` calls behind it. Set
+/// `BYTEWAX_STATEFUL_BATCH_MAX_ITEMS_PER_KEY` to an integer to instead
+/// only hand over that many items per key per activation, re-queuing
+/// the rest via the same mechanism as [`StatefulBatchLogic::on_batch`]
+/// itself uses to re-deliver items it didn't get a chance to consume,
+/// so an activation makes progress across many keys instead of
+/// draining a single hot one. Off by default. Checked once and cached,
+/// since this isn't something you'd toggle mid-run.
+fn fairness_max_items_per_key() -> Option<usize> {
+    static MAX_ITEMS: std::sync::OnceLock<Option<usize>> = std::sync::OnceLock::new();
+    *MAX_ITEMS.get_or_init(|| {
+        std::env::var("BYTEWAX_STATEFUL_BATCH_MAX_ITEMS_PER_KEY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+    })
+}
+
+/// Partition function used to route state keys to the worker that
+/// owns them in [`StatefulBatchOp::stateful_batch`].
+///
+/// Defaults to the same [`SeaHasher`]-based hash used elsewhere in the
+/// routing code. Unlike `std`'s `DefaultHasher`, `SeaHasher`'s output
+/// is stable across Rust versions and platforms, so partition
+/// assignment doesn't shift out from under a recovery snapshot just
+/// because the dataflow was rebuilt. Can be overridden with a Python
+/// callable so callers with skewed key distributions can control
+/// state placement.
+///
+/// The same instance is used for both the live-item and
+/// resume-snapshot partitioning, so a key's state is always loaded on
+/// the same worker that will receive live items for it.
+#[derive(Clone)]
+enum SelfPartitionFn {
+    Default(BuildHasherDefault<SeaHasher>),
+    Seeded(SeededSeaHasher),
+    Custom(TdPyCallable),
+}
+
+impl PartitionFn<StateKey> for SelfPartitionFn {
+    fn assign(&self, key: &StateKey) -> usize {
+        match self {
+            Self::Default(pf) => pf.assign(key),
+            Self::Seeded(pf) => pf.assign(key),
+            Self::Custom(callable) => Python::with_gil(|py| {
+                unwrap_any!(|| -> PyResult<usize> {
+                    callable
+                        .bind(py)
+                        .call1((key.0.clone(),))
+                        .reraise("error calling custom `stateful_batch` partition function")?
+                        .extract::<usize>()
+                        .reraise("custom `stateful_batch` partition function must return an `int`")
+                }())
+            }),
+        }
+    }
+}
+
+/// A [`SeaHasher`]-based [`BuildHasher`] with the seed mixed in, so
+/// `stateful_batch`'s default self-partitioning can be deliberately
+/// reshuffled (e.g. to fix skew) without switching to a custom
+/// `partition_fn`.
+///
+/// Changing the seed invalidates the placement recorded in any
+/// existing resume snapshot: a key will hash to a different worker
+/// than the one that owns its current state, so this must be paired
+/// with the rescale/repartition recovery path rather than a plain
+/// resume.
+#[derive(Clone, Copy)]
+struct SeededSeaHasher(u64);
+
+impl BuildHasher for SeededSeaHasher {
+    type Hasher = SeaHasher;
+
+    fn build_hasher(&self) -> SeaHasher {
+        SeaHasher::with_seeds(self.0, self.0, self.0, self.0)
+    }
+}
+
 impl<S> StatefulBatchOp<S> for Stream<S, TdPyAny>
 where
     S: Scope<Timestamp = u64>,
 {
+    #[allow(clippy::too_many_arguments)]
     fn stateful_batch(
         &self,
         _py: Python,
@@ -557,26 +2120,41 @@ where
         builder: TdPyCallable,
         resume_epoch: ResumeEpoch,
         loads: &Stream<S, Snapshot>,
-    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, Snapshot>)> {
+        workers: &Stream<S, WorkerIndex>,
+        ctrl: &Stream<S, TdPyAny>,
+        emit_discards: bool,
+        partition_fn: Option<TdPyCallable>,
+        partition_seed: Option<u64>,
+        notify_coalesce_interval: Option<TimeDelta>,
+        snapshot_interval: Option<u64>,
+        resume_lazily: bool,
+    ) -> PyResult<(Stream<S, TdPyAny>, Stream<S, TdPyAny>, Stream<S, Snapshot>)> {
         let this_worker = self.scope().w_index();
+        let notify_coalesce_interval = notify_coalesce_interval
+            .and_then(|interval| interval.to_std().ok())
+            .filter(|interval| !interval.is_zero());
+        let snapshot_interval = snapshot_interval.filter(|&interval| interval > 0);
 
         let loads = loads.filter_snaps(step_id.clone());
-        // We have a "partition" per worker. List all workers.
-        let workers = self.scope().w_count().iter().to_stream(&mut self.scope());
-        // TODO: Could expose this above.
-        let self_pf = BuildHasherDefault::<DefaultHasher>::default();
-        let loads_pf = BuildHasherDefault::<DefaultHasher>::default();
+        let pf = match (partition_fn, partition_seed) {
+            (Some(callable), _) => SelfPartitionFn::Custom(callable),
+            (None, Some(seed)) => SelfPartitionFn::Seeded(SeededSeaHasher(seed)),
+            (None, None) => SelfPartitionFn::Default(BuildHasherDefault::<SeaHasher>::default()),
+        };
+        let self_pf = pf.clone();
+        let loads_pf = pf;
         let partd_self = self.extract_key(step_id.clone()).partition(
             format!("{step_id}.self_partition"),
-            &workers,
+            workers,
             self_pf,
         );
-        let partd_loads = loads.partition(format!("{step_id}.load_partition"), &workers, loads_pf);
+        let partd_loads = loads.partition(format!("{step_id}.load_partition"), workers, loads_pf);
 
         let op_name = format!("{step_id}.stateful_batch");
         let mut op_builder = OperatorBuilder::new(op_name.clone(), self.scope());
 
         let (mut kv_downstream_output, kv_downstream) = op_builder.new_output();
+        let (mut discards_output, discards) = op_builder.new_output();
         let (mut snaps_output, snaps) = op_builder.new_output();
 
         let mut input_handle = op_builder.new_input_connection(
@@ -589,6 +2167,7 @@ where
             routed_exchange(),
             vec![Antichain::from_elem(0), Antichain::from_elem(0)],
         );
+        let mut ctrl_handle = op_builder.new_input(ctrl, Pipeline);
 
         let info = op_builder.operator_info();
         let activator = self.scope().activator_for(&info.address[..]);
@@ -614,6 +2193,10 @@ where
             .f64_histogram("stateful_batch_on_eof_duration_seconds")
             .with_description("`StatefulBatchLogic.on_eof` duration in seconds")
             .init();
+        let on_epoch_close_histogram = meter
+            .f64_histogram("stateful_batch_on_epoch_close_duration_seconds")
+            .with_description("`StatefulBatchLogic.on_epoch_close` duration in seconds")
+            .init();
         let notify_at_histogram = meter
             .f64_histogram("stateful_batch_notify_at_duration_seconds")
             .with_description("`StatefulBatchLogic.notify_at` duration in seconds")
@@ -622,10 +2205,78 @@ where
             .f64_histogram("snapshot_duration_seconds")
             .with_description("`snapshot` duration in seconds")
             .init();
+        let should_snapshot_histogram = meter
+            .f64_histogram("stateful_batch_should_snapshot_duration_seconds")
+            .with_description("`StatefulBatchLogic.should_snapshot` duration in seconds")
+            .init();
+        let snapshots_written = meter
+            .u64_counter("snapshots_written")
+            .with_description("number of key snapshots written per epoch")
+            .init();
+        let gil_wait_histogram = meter
+            .f64_histogram("gil_wait_duration_seconds")
+            .with_description("time spent waiting to acquire the GIL")
+            .init();
+        let epochs_closed = meter
+            .u64_counter("epochs_closed")
+            .with_description("number of epochs this step has closed")
+            .init();
+        let callback_timeout_counter = meter
+            .u64_counter("callback_timeout")
+            .with_description("number of times a callback ran longer than BYTEWAX_CALLBACK_TIMEOUT_SECONDS")
+            .init();
+        let fairness_requeued_item_count = meter
+            .u64_counter("stateful_batch_fairness_requeued_item_count")
+            .with_description(
+                "number of items re-queued by BYTEWAX_STATEFUL_BATCH_MAX_ITEMS_PER_KEY to let other keys progress this activation",
+            )
+            .init();
         let labels = vec![
             KeyValue::new("step_id", step_id.0.to_string()),
             KeyValue::new("worker_index", this_worker.0.to_string()),
         ];
+        let frontier_epoch = Arc::new(AtomicU64::new(0));
+        {
+            let frontier_epoch = Arc::clone(&frontier_epoch);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("operator_frontier_epoch")
+                .with_description("input frontier epoch of this operator, for lag monitoring")
+                .with_callback(move |observer| {
+                    observer.observe(frontier_epoch.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
+        let pending_epochs = Arc::new(AtomicU64::new(0));
+        let pending_items = Arc::new(AtomicU64::new(0));
+        {
+            let pending_epochs = Arc::clone(&pending_epochs);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("notificator_pending_epochs")
+                .with_description(
+                    "number of distinct epochs of input buffered awaiting the \
+                    frontier to advance, for detecting a step falling behind",
+                )
+                .with_callback(move |observer| {
+                    observer.observe(pending_epochs.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
+        {
+            let pending_items = Arc::clone(&pending_items);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("notificator_pending_items")
+                .with_description(
+                    "total number of items buffered across all pending epochs, \
+                    for detecting a step falling behind",
+                )
+                .with_callback(move |observer| {
+                    observer.observe(pending_items.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
 
         op_builder.build(move |mut init_caps| {
             // We have to retain separate capabilities
@@ -634,18 +2285,37 @@ where
             // In reverse order because of how [`Vec::pop`] removes
             // from back.
             let mut snap_cap = init_caps.pop();
+            let mut discards_cap = init_caps.pop();
             let mut kv_downstream_cap = init_caps.pop();
 
             // State for each key. There is only a single state for
             // each key representing the state at the frontier epoch;
             // we only modify state carefully in epoch order once we
             // know we won't be getting any input on closed epochs.
+            //
+            // This is a `BTreeMap`, not a `HashMap`, on purpose:
+            // `on_batch`, `on_notify`, and `on_eof` below all iterate
+            // keys in `StateKey`'s sorted order, so given the same
+            // input, output is byte-for-byte reproducible run to run
+            // rather than depending on hash iteration order.
             let mut logics: BTreeMap<StateKey, StatefulBatchLogic> = BTreeMap::new();
             // Contains the last known return value for
             // `logic.notify_at` for each key (if any). We don't
             // snapshot this because the logic itself should contain
             // any notify times within.
+            //
+            // Also a `BTreeMap` so `on_notify` below wakes keys up in
+            // sorted order, matching `on_batch` and `on_eof`.
             let mut sched_cache: BTreeMap<StateKey, DateTime<Utc>> = BTreeMap::new();
+            // Only populated when `resume_lazily`. Holds resume state
+            // for keys loaded on recovery but not yet materialized
+            // into `logics`, deferring the `builder` call (and the
+            // `notify_at` call needed to populate `sched_cache`) until
+            // the key is first touched by a live item. This trades
+            // away resume-time wake-ups for keys that stay idle after
+            // resume, so it's only safe for logic that doesn't rely on
+            // `notify_at` firing without a new item arriving first.
+            let mut pending_resume: BTreeMap<StateKey, TdPyAny> = BTreeMap::new();
 
             // Here we have "buffers" that store items across
             // activations.
@@ -657,19 +2327,41 @@ where
             // removed from it as the input frontier progresses.
             let mut inbuf = InBuffer::new();
             let mut loads_inbuf = InBuffer::new();
+            // Checkpoint requests received on `ctrl`, buffered the
+            // same way so one can arrive for an epoch before that
+            // epoch's data does.
+            let mut ctrl_inbuf = InBuffer::new();
             // Persistent across activations buffer of what keys were
             // awoken during the most recent epoch. This is used to
             // only snapshot state of keys that could have resulted in
             // state modifications. This is drained after each epoch
             // is processed.
             let mut awoken_keys_this_epoch_buffer: BTreeSet<StateKey> = BTreeSet::new();
+            // Count of keys resumed from a snapshot so we can log a
+            // single diagnostic summary once loading finishes, rather
+            // than a `trace!` per key.
+            let mut resumed_key_count: u64 = 0;
+            let mut logged_resume = false;
 
             move |input_frontiers| {
-                tracing::debug_span!("operator", operator = op_name).in_scope(|| {
-                    if let (Some(output_cap), Some(state_update_cap)) =
-                        (kv_downstream_cap.as_mut(), snap_cap.as_mut())
-                    {
+                let span = tracing::debug_span!(
+                    "operator",
+                    operator = op_name,
+                    epoch = tracing::field::Empty,
+                    item_count = tracing::field::Empty,
+                );
+                span.in_scope(|| {
+                    if let Some(epoch) = input_frontiers.simplify() {
+                        frontier_epoch.store(*epoch, Ordering::Relaxed);
+                    }
+
+                    if let (Some(output_cap), Some(discards_cap), Some(state_update_cap)) = (
+                        kv_downstream_cap.as_mut(),
+                        discards_cap.as_mut(),
+                        snap_cap.as_mut(),
+                    ) {
                         assert!(output_cap.time() == state_update_cap.time());
+                        assert!(output_cap.time() == discards_cap.time());
 
                         let now = chrono::offset::Utc::now();
 
@@ -683,6 +2375,12 @@ where
                             let epoch = cap.time();
                             loads_inbuf.extend(*epoch, incoming);
                         });
+                        ctrl_handle.for_each(|cap, incoming| {
+                            let epoch = cap.time();
+                            ctrl_inbuf.extend(*epoch, incoming);
+                        });
+                        pending_epochs.store(inbuf.pending_epoch_count() as u64, Ordering::Relaxed);
+                        pending_items.store(inbuf.pending_item_count() as u64, Ordering::Relaxed);
 
                         let last_output_epoch = *output_cap.time();
                         let frontier_epoch = input_frontiers
@@ -711,6 +2409,7 @@ where
                         // for.
                         process_epochs.extend(inbuf.epochs());
                         process_epochs.extend(loads_inbuf.epochs());
+                        process_epochs.extend(ctrl_inbuf.epochs());
 
                         // Filter out epochs that are not closed; the
                         // state at the beginning of those epochs are
@@ -728,6 +2427,7 @@ where
                         }
 
                         let mut kv_downstream_handle = kv_downstream_output.activate();
+                        let mut discards_handle = discards_output.activate();
                         let mut snaps_handle = snaps_output.activate();
                         // For each epoch in order.
                         for epoch in process_epochs {
@@ -740,52 +2440,115 @@ where
                             // changes in epoch order" to the state
                             // cache.
                             output_cap.downgrade(&epoch);
+                            discards_cap.downgrade(&epoch);
                             state_update_cap.downgrade(&epoch);
 
                             let mut kv_downstream_session =
                                 kv_downstream_handle.session(&output_cap);
+                            let mut discards_session = discards_handle.session(&discards_cap);
 
                             // Keep track of all keys that had logic
                             // methods called so we know which to call
                             // `notify_at` on.
                             let mut awoken_keys_this_activation: BTreeSet<StateKey> = BTreeSet::new();
 
+                            // `on_batch`, `on_notify`, `on_eof`, and the
+                            // `notify_at` re-scheduling that follows them
+                            // are all called for this epoch under a
+                            // single GIL acquisition below, rather than
+                            // one each, since they run back-to-back on
+                            // every activation and each would otherwise
+                            // pay its own acquire/release.
+                            unwrap_any!(with_gil_timed!(
+                                gil_wait_histogram,
+                                labels,
+                                |py| -> PyResult<()> {
+                            let builder = builder.bind(py);
+
                             // First, call `on_batch` for all the input
                             // items.
                             if let Some(items) = inbuf.remove(&epoch) {
+                                span.record("epoch", tracing::field::debug(&epoch));
+                                span.record("item_count", items.len());
                                 item_inp_count.add(items.len() as u64, &labels);
 
+                                // `BTreeMap` so `on_batch` below is called key-sorted, see
+                                // the note on `logics` above.
                                 let mut keyed_items: BTreeMap<StateKey, Vec<PyObject>> = BTreeMap::new();
                                 for (worker, (key, value)) in items {
                                     assert!(worker == this_worker);
                                     keyed_items.entry(key).or_default().push(PyObject::from(value));
                                 }
 
-                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
-                                    let builder = builder.bind(py);
+                                for (key, mut values) in keyed_items {
+                                        // If fair scheduling is on and this key
+                                        // has more items than the cap, only
+                                        // hand over the first
+                                        // `max_items_per_key` of them this
+                                        // activation; re-queue the rest so
+                                        // other keys get a turn instead of
+                                        // this one hot key monopolizing the
+                                        // activation. Woken back up
+                                        // immediately, same as the
+                                        // `on_batch`-returned `retry` case
+                                        // below.
+                                        if let Some(max_items_per_key) = fairness_max_items_per_key() {
+                                            if values.len() > max_items_per_key {
+                                                let overflow = values.split_off(max_items_per_key);
+                                                fairness_requeued_item_count
+                                                    .add(overflow.len() as u64, &labels);
+                                                let overflow_items = overflow
+                                                    .into_iter()
+                                                    .map(|value| (this_worker, (key.clone(), TdPyAny::from(value))))
+                                                    .collect();
+                                                inbuf.requeue(epoch, overflow_items);
+                                                activator.activate();
+                                            }
+                                        }
 
-                                    for (key, values) in keyed_items {
                                         // Ok, let's actually run the logic code!
                                         // Pull out or build the logic for the
-                                        // current key.
+                                        // current key. If `resume_lazily` left
+                                        // this key's resume state staged
+                                        // rather than building it at load
+                                        // time, this is the first touch that
+                                        // materializes it; the `notify_at`
+                                        // scheduling loop below picks up its
+                                        // schedule the same as any other key
+                                        // awoken this activation.
+                                        let resume_state = pending_resume.remove(&key);
                                         let logic =
                                             logics.entry(key.clone()).or_insert_with(|| {
                                                 unwrap_any!((|| {
                                                     builder
-                                                        .call1((None::<PyObject>, ))?
+                                                        .call1((resume_state.map(PyObject::from),))?
                                                         .extract::<StatefulBatchLogic>()
-                                                })(
-                                                ))
+                                                })()
+                                                .reraise_with(|| format!(
+                                                    "error calling `builder` in step {step_id} for key {key}"
+                                                )))
                                             });
 
-                                        let (output, is_complete) = with_timer!(
+                                        let (output, is_complete, retry, merge_from) = with_timer!(
                                             on_batch_histogram,
                                             labels,
-                                            logic
-                                                .on_batch(py, values)
+                                            with_watchdog!(
+                                                callback_timeout_counter,
+                                                labels,
+                                                format!(
+                                                    "`StatefulBatchLogic.on_batch` in step {step_id} for key {key}"
+                                                ),
+                                                std::panic::catch_unwind(std::panic::AssertUnwindSafe(
+                                                    move || logic.on_batch(py, values)
+                                                ))
+                                                .map_err(|payload| tracked_err::<PyRuntimeError>(&format!(
+                                                    "panic calling `StatefulBatchLogic.on_batch` in step {step_id} for key {key}: {}",
+                                                    panic_msg(&*payload)
+                                                )))?
                                                 .reraise_with(|| format!(
                                                     "error calling `StatefulBatchLogic.on_batch` in step {step_id} for key {key}"
                                                 ))?
+                                            )
                                         );
 
                                         item_out_count.add(output.len() as u64, &labels);
@@ -794,15 +2557,89 @@ where
                                         }
 
                                         if let IsComplete::Discard = is_complete {
+                                            if !merge_from.is_empty() {
+                                                return Err(tracked_err::<PyRuntimeError>(&format!(
+                                                    "`StatefulBatchLogic.on_batch` in step {step_id} for key {key} \
+                                                    requested merging {merge_from:?} while also discarding itself"
+                                                )));
+                                            }
                                             logics.remove(&key);
                                             sched_cache.remove(&key);
+                                            if emit_discards {
+                                                discards_session
+                                                    .give((key.clone(), TdPyAny::from(epoch.into_py(py))));
+                                            }
                                         }
 
-                                        awoken_keys_this_activation.insert(key);
-                                    }
+                                        // The logic requested absorbing one
+                                        // or more donor keys' state. Snapshot
+                                        // each donor (they must already be
+                                        // resident on this worker; merging
+                                        // across workers isn't supported),
+                                        // hand it to this key's `merge`, then
+                                        // discard the donor the same way any
+                                        // other `IsComplete::Discard` is.
+                                        if !merge_from.is_empty() {
+                                            let mut donor_states = Vec::new();
+                                            for donor_key in merge_from {
+                                                let donor_key = StateKey(donor_key);
+                                                if donor_key == key {
+                                                    return Err(tracked_err::<PyRuntimeError>(&format!(
+                                                        "`StatefulBatchLogic.on_batch` in step {step_id} for key {key} \
+                                                        requested merging itself"
+                                                    )));
+                                                }
+                                                let donor_logic = logics.remove(&donor_key).ok_or_else(|| {
+                                                    tracked_err::<PyRuntimeError>(&format!(
+                                                        "`StatefulBatchLogic.on_batch` in step {step_id} for key {key} \
+                                                        requested merging key {donor_key}, but it isn't resident on \
+                                                        this worker; merging only works across keys already \
+                                                        co-located by partitioning"
+                                                    ))
+                                                })?;
+                                                let donor_state = donor_logic.snapshot(py).reraise_with(|| format!(
+                                                    "error calling `StatefulBatchLogic.snapshot` in step {step_id} \
+                                                    for donor key {donor_key} during merge into key {key}"
+                                                ))?;
+                                                donor_states.push((donor_key, donor_state));
+                                            }
 
-                                    Ok(())
-                                }));
+                                            // We already bailed out above if
+                                            // `is_complete` was `Discard`, so
+                                            // `key` is still resident.
+                                            let logic = logics.get(&key).unwrap();
+                                            for (donor_key, donor_state) in donor_states {
+                                                logic.merge(py, donor_state).reraise_with(|| format!(
+                                                    "error calling `StatefulBatchLogic.merge` in step {step_id} \
+                                                    for key {key} merging donor key {donor_key}"
+                                                ))?;
+                                                sched_cache.remove(&donor_key);
+                                                if emit_discards {
+                                                    discards_session
+                                                        .give((donor_key.clone(), TdPyAny::from(epoch.into_py(py))));
+                                                }
+                                                awoken_keys_this_activation.insert(donor_key);
+                                            }
+                                        }
+
+                                        // The logic didn't fully drain this
+                                        // batch; have it re-delivered on a
+                                        // later activation instead of
+                                        // forcing the logic to buffer it.
+                                        // Wake ourselves back up immediately
+                                        // to retry rather than waiting for
+                                        // more upstream input or a notify_at.
+                                        if !retry.is_empty() {
+                                            let retry_items = retry
+                                                .into_iter()
+                                                .map(|value| (this_worker, (key.clone(), TdPyAny::from(value))))
+                                                .collect();
+                                            inbuf.requeue(epoch, retry_items);
+                                            activator.activate();
+                                        }
+
+                                    awoken_keys_this_activation.insert(key);
+                                }
                             }
 
                             // Then call all logic that has a due
@@ -810,51 +2647,61 @@ where
                             let notify_keys: Vec<_> = sched_cache
                                 .iter()
                                 .filter(|(_key, sched)| **sched <= now)
-                                .map(|(key, sched)| (key.clone(), *sched))
+                                .map(|(key, _sched)| key.clone())
                                 .collect();
                             if !notify_keys.is_empty() {
-                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
-                                    for (key, _sched) in notify_keys {
-                                        // We should always have a
-                                        // logic for anything in
-                                        // `sched_cache`. If not, we
-                                        // forgot to remove it when we
-                                        // cleared the logic.
-                                        let logic = logics.get(&key).unwrap();
-
-                                        let (output, is_complete) = with_timer!(
-                                            on_notify_histogram,
-                                            labels,
-                                            logic.on_notify(py).reraise_with(|| format!(
-                                                "error calling `StatefulBatchLogic.on_notify` in {step_id} for key {key}"
-                                            ))?
-                                        );
+                                // We should always have a logic for
+                                // anything in `sched_cache`. If not,
+                                // we forgot to remove it when we
+                                // cleared the logic.
+                                let due: Vec<(String, PyObject)> = notify_keys
+                                    .iter()
+                                    .map(|key| (key.0.clone(), logics.get(key).unwrap().0.clone_ref(py)))
+                                    .collect();
+                                // Any due key's logic works as the
+                                // receiver; `on_notify_batch` is a
+                                // `classmethod`.
+                                let representative = logics.get(&notify_keys[0]).unwrap();
+
+                                let mut results = with_timer!(
+                                    on_notify_histogram,
+                                    labels,
+                                    representative.on_notify_batch(py, due).reraise_with(|| format!(
+                                        "error calling `StatefulBatchLogic.on_notify_batch` in {step_id}"
+                                    ))?
+                                );
+
+                                for key in notify_keys {
+                                    let (output, is_complete) = results.remove(&key.0).ok_or_else(|| {
+                                        PyValueError::new_err(format!(
+                                            "`StatefulBatchLogic.on_notify_batch` in {step_id} did not return a result for key {key}"
+                                        ))
+                                    })?;
 
-                                        item_out_count.add(output.len() as u64, &labels);
-                                        for value in output {
-                                            kv_downstream_session.give((key.clone(), TdPyAny::from(value)));
-                                        }
+                                    item_out_count.add(output.len() as u64, &labels);
+                                    for value in output {
+                                        kv_downstream_session.give((key.clone(), TdPyAny::from(value)));
+                                    }
 
-                                        if let IsComplete::Discard = is_complete {
-                                            logics.remove(&key);
-                                            sched_cache.remove(&key);
-                                        } else {
-                                            // Even if we don't
-                                            // discard the logic, the
-                                            // previous scheduled
-                                            // notification only
-                                            // should fire once. The
-                                            // logic can re-schedule
-                                            // it by still returning
-                                            // it in `notify_at`.
-                                            sched_cache.remove(&key);
+                                    if let IsComplete::Discard = is_complete {
+                                        logics.remove(&key);
+                                        sched_cache.remove(&key);
+                                        if emit_discards {
+                                            discards_session
+                                                .give((key.clone(), TdPyAny::from(epoch.into_py(py))));
                                         }
-
-                                        awoken_keys_this_activation.insert(key);
+                                    } else {
+                                        // Even if we don't discard
+                                        // the logic, the previous
+                                        // scheduled notification only
+                                        // should fire once. The logic
+                                        // can re-schedule it by still
+                                        // returning it in `notify_at`.
+                                        sched_cache.remove(&key);
                                     }
 
-                                    Ok(())
-                                }));
+                                    awoken_keys_this_activation.insert(key);
+                                }
                             }
 
                             // Then if EOF, call all logic that still
@@ -862,8 +2709,7 @@ where
                             if input_frontiers.is_eof() {
                                 let mut discarded_keys = Vec::new();
 
-                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
-                                    for (key, logic) in logics.iter() {
+                                for (key, logic) in logics.iter() {
                                         let (output, is_complete) = with_timer!(
                                             on_eof_histogram,
                                             labels,
@@ -881,15 +2727,16 @@ where
                                             discarded_keys.push(key.clone());
                                         }
 
-                                        awoken_keys_this_activation.insert(key.clone());
-                                    }
-
-                                    Ok(())
-                                }));
+                                    awoken_keys_this_activation.insert(key.clone());
+                                }
 
                                 for key in discarded_keys {
                                     logics.remove(&key);
                                     sched_cache.remove(&key);
+                                    if emit_discards {
+                                        discards_session
+                                            .give((key.clone(), TdPyAny::from(epoch.into_py(py))));
+                                    }
                                 }
                             }
 
@@ -897,8 +2744,7 @@ where
                             // update the next scheduled notification
                             // times.
                             if !awoken_keys_this_activation.is_empty() {
-                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
-                                    for key in awoken_keys_this_activation.iter() {
+                                for key in awoken_keys_this_activation.iter() {
                                         // It's possible the logic was
                                         // discarded on a previous
                                         // activation but the epoch
@@ -913,27 +2759,127 @@ where
                                                     format!("error calling `StatefulBatchLogic.notify_at` in {step_id} for key {key}")
                                                 })?
                                             );
-                                            if let Some(sched) = sched {
-                                                sched_cache.insert(key.clone(), sched);
-                                            }
+                                        if let Some(sched) = sched {
+                                            sched_cache.insert(key.clone(), sched);
                                         }
                                     }
-
-                                    Ok(())
-                                }));
+                                }
 
                                 // Now mark all these keys as aowken
                                 // in the epoch so snapshotting works.
                                 awoken_keys_this_epoch_buffer.extend(awoken_keys_this_activation);
                             }
 
+                            Ok(())
+                                }
+                            ));
+
+                            // An explicit checkpoint request on `ctrl`
+                            // snapshots every key awoken so far this
+                            // epoch right now, rather than waiting for
+                            // the epoch to close or for
+                            // `snapshot_interval` to come due. This is
+                            // purely additive: the on-close snapshot
+                            // below still runs as normal, so a
+                            // duplicate or stale-relative-to-it write
+                            // here is harmless.
+                            if ctrl_inbuf.remove(&epoch).is_some() {
+                                let mut snaps_session = snaps_handle.session(&state_update_cap);
+                                unwrap_any!(with_gil_timed!(
+                                    gil_wait_histogram,
+                                    labels,
+                                    |py| -> PyResult<()> {
+                                        for key in &awoken_keys_this_epoch_buffer {
+                                            if let Some(logic) = logics.get(key) {
+                                                let state = with_timer!(
+                                                    snapshot_histogram,
+                                                    labels,
+                                                    logic.snapshot(py).reraise_with(|| {
+                                                        format!(
+                                                            "error calling `StatefulBatchLogic.snapshot` \
+                                                            in {step_id} for key {key} during explicit checkpoint"
+                                                        )
+                                                    })?
+                                                );
+                                                let snap = Snapshot(
+                                                    step_id.clone(),
+                                                    key.clone(),
+                                                    StateChange::Upsert(TdPyAny::from(state)),
+                                                );
+                                                snaps_session.give(snap);
+                                                snapshots_written.add(1, &labels);
+                                            }
+                                        }
+                                        Ok(())
+                                    }
+                                ));
+                            }
+
                             // Snapshot and output state changes.
                             if input_frontiers.is_closed(&epoch) {
+                                epochs_closed.add(1, &labels);
+
+                                // Give every key awoken so far this
+                                // epoch a chance to react to the epoch
+                                // closing, e.g. to flush state that's
+                                // only meaningful within a single
+                                // epoch, before it's snapshotted below.
+                                let mut discarded_keys = Vec::new();
+                                unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
+                                    for key in &awoken_keys_this_epoch_buffer {
+                                        if let Some(logic) = logics.get(key) {
+                                            let (output, is_complete) = with_timer!(
+                                                on_epoch_close_histogram,
+                                                labels,
+                                                logic.on_epoch_close(py, epoch.clone().into_py(py)).reraise_with(|| format!(
+                                                    "error calling `StatefulBatchLogic.on_epoch_close` in {step_id} for key {key}"
+                                                ))?
+                                            );
+
+                                            item_out_count.add(output.len() as u64, &labels);
+                                            for value in output {
+                                                kv_downstream_session.give((key.clone(), TdPyAny::from(value)));
+                                            }
+
+                                            if let IsComplete::Discard = is_complete {
+                                                discarded_keys.push(key.clone());
+                                            }
+                                        }
+                                    }
+
+                                    for key in &discarded_keys {
+                                        logics.remove(key);
+                                        sched_cache.remove(key);
+                                        if emit_discards {
+                                            discards_session
+                                                .give((key.clone(), TdPyAny::from(epoch.into_py(py))));
+                                        }
+                                    }
+
+                                    Ok(())
+                                }));
+
                                 // Snapshot before loads. If we have an
                                 // incoming load, it means we have
                                 // recovery state already at the end of
                                 // the epoch.
 
+                                // If `snapshot_interval` is set, skip
+                                // writing out still-live state on
+                                // epochs that aren't due, to cut down
+                                // on snapshot volume for steps with
+                                // expensive-to-serialize state. Always
+                                // write discards though: they're rare,
+                                // and skipping one would leave an
+                                // obsolete upsert as the most recent
+                                // snapshot on disk. Always write on
+                                // EOF too, so a graceful shutdown
+                                // doesn't lose the final state.
+                                let snapshot_due = snapshot_interval
+                                    .map(|interval| epoch % interval == 0)
+                                    .unwrap_or(true)
+                                    || input_frontiers.is_eof();
+
                                 let mut snaps_session = snaps_handle.session(&state_update_cap);
 
                                 // Go through all keys awoken in this
@@ -942,9 +2888,31 @@ where
                                 unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
                                     // Finally drain
                                     // `awoken_keys_buffer` since the
-                                    // epoch is over.
+                                    // epoch is over. Keys whose logic
+                                    // vetoes the snapshot via
+                                    // `should_snapshot` are put back,
+                                    // to be retried at the next epoch
+                                    // close, unless this is EOF and
+                                    // there won't be a next one.
+                                    let mut still_pending = Vec::new();
                                     for key in std::mem::take(&mut awoken_keys_this_epoch_buffer) {
                                         let change = if let Some(logic) = logics.get(&key) {
+                                            if !snapshot_due {
+                                                continue;
+                                            }
+                                            if !input_frontiers.is_eof() {
+                                                let should_snapshot = with_timer!(
+                                                    should_snapshot_histogram,
+                                                    labels,
+                                                    logic.should_snapshot(py).reraise_with(|| {
+                                                        format!("error calling `StatefulBatchLogic.should_snapshot` in {step_id} for key {key}")
+                                                    })?
+                                                );
+                                                if !should_snapshot {
+                                                    still_pending.push(key);
+                                                    continue;
+                                                }
+                                            }
                                             let state = with_timer!(
                                                 snapshot_histogram,
                                                 labels,
@@ -965,8 +2933,11 @@ where
                                         };
                                         let snap = Snapshot(step_id.clone(), key, change);
                                         snaps_session.give(snap);
+                                        snapshots_written.add(1, &labels);
                                     }
 
+                                    awoken_keys_this_epoch_buffer.extend(still_pending);
+
                                     Ok(())
                                 }));
 
@@ -981,19 +2952,54 @@ where
                                             assert!(worker == this_worker);
                                             match change {
                                                 StateChange::Upsert(state) => {
-                                                    let state: PyObject = state.into();
-
-                                                    let logic = builder
-                                                        .call1((Some(state),))?
-                                                        .extract::<StatefulBatchLogic>()?;
-                                                    if let Some(notify_at) = logic.notify_at(py)? {
-                                                        sched_cache.insert(key.clone(), notify_at);
+                                                    if resume_lazily {
+                                                        // Defer the `builder`
+                                                        // (and `notify_at`)
+                                                        // call until the key
+                                                        // is first touched;
+                                                        // see `pending_resume`.
+                                                        pending_resume.insert(key, state);
+                                                        resumed_key_count += 1;
+                                                    } else {
+                                                        let state: PyObject = state.into();
+
+                                                        let logic = (|| {
+                                                            builder
+                                                                .call1((Some(state),))?
+                                                                .extract::<StatefulBatchLogic>()
+                                                        })()
+                                                        .reraise_with(|| format!(
+                                                            "error calling `builder` in step {step_id} for key {key}"
+                                                        ))?;
+                                                        if let Some(notify_at) = logic.notify_at(py)? {
+                                                            sched_cache.insert(key.clone(), notify_at);
+                                                        }
+                                                        logics.insert(key, logic);
+                                                        resumed_key_count += 1;
+                                                    }
+                                                    if let Some(interval) =
+                                                        resume_progress_log_interval()
+                                                    {
+                                                        if interval > 0
+                                                            && resumed_key_count % interval == 0
+                                                        {
+                                                            let verb = if resume_lazily {
+                                                                "staged"
+                                                            } else {
+                                                                "loaded"
+                                                            };
+                                                            tracing::info!(
+                                                                "{step_id} resuming: \
+                                                                {resumed_key_count} keys {verb} \
+                                                                so far"
+                                                            );
+                                                        }
                                                     }
-                                                    logics.insert(key, logic);
                                                 }
                                                 StateChange::Discard => {
                                                     logics.remove(&key);
                                                     sched_cache.remove(&key);
+                                                    pending_resume.remove(&key);
                                                 }
                                             }
                                         }
@@ -1010,6 +3016,20 @@ where
                             // items, like on window timeout, ensure we FFWD to the
                             // resume epoch.
                             init_caps.downgrade_all(&resume_epoch.0);
+
+                            if !logged_resume {
+                                if resume_lazily {
+                                    tracing::info!(
+                                        "{step_id} resumed from {resume_epoch:?} with \
+                                        {resumed_key_count} keys staged for lazy loading"
+                                    );
+                                } else {
+                                    tracing::info!(
+                                        "{step_id} resumed from {resume_epoch:?} with {resumed_key_count} keys"
+                                    );
+                                }
+                                logged_resume = true;
+                            }
                         }
 
                         // Schedule operator activation at the soonest
@@ -1017,14 +3037,26 @@ where
                         if let Some(next_notify_at) =
                             sched_cache.values().map(|notify_at| *notify_at - now).min()
                         {
-                            activator.activate_after(
-                                next_notify_at.to_std().unwrap_or(std::time::Duration::ZERO),
-                            );
+                            let mut wait =
+                                next_notify_at.to_std().unwrap_or(std::time::Duration::ZERO);
+                            // Round the wait up to the next multiple of
+                            // the coalescing window, if any, so keys
+                            // due within the same window share a
+                            // single activation instead of each
+                            // causing their own.
+                            if let Some(window) = notify_coalesce_interval {
+                                let extra = wait.as_nanos() % window.as_nanos();
+                                if extra != 0 {
+                                    wait += window - std::time::Duration::from_nanos(extra as u64);
+                                }
+                            }
+                            activator.activate_after(wait);
                         }
                     }
 
                     if input_frontiers.is_eof() {
                         kv_downstream_cap = None;
+                        discards_cap = None;
                         snap_cap = None;
                     }
                 });
@@ -1032,7 +3064,38 @@ where
         });
 
         let downstream = kv_downstream.wrap_key();
+        let discards = discards.wrap_key();
 
-        Ok((downstream, snaps))
+        Ok((downstream, discards, snaps))
     }
 }
+
+/// `stateful_batch` builds `keyed_items`, `logics`, and `sched_cache`
+/// as `BTreeMap`s so `on_batch`, `on_notify`, and `on_eof` all visit
+/// keys in the same order, byte-for-byte reproducibly, regardless of
+/// insertion order. This locks in that they all sort the same way.
+#[test]
+fn stateful_batch_key_iteration_order_is_unified() {
+    let unsorted = vec![
+        StateKey("c".to_string()),
+        StateKey("a".to_string()),
+        StateKey("b".to_string()),
+    ];
+
+    let keyed_items: BTreeMap<_, _> = unsorted.iter().cloned().map(|k| (k, ())).collect();
+    let logics: BTreeMap<_, _> = unsorted.iter().cloned().map(|k| (k, ())).collect();
+    let sched_cache: BTreeMap<_, _> = unsorted.iter().cloned().map(|k| (k, ())).collect();
+
+    let expected: Vec<_> = {
+        let mut sorted = unsorted;
+        sorted.sort();
+        sorted
+    };
+
+    assert_eq!(
+        keyed_items.keys().cloned().collect::<Vec<_>>(),
+        expected
+    );
+    assert_eq!(logics.keys().cloned().collect::<Vec<_>>(), expected);
+    assert_eq!(sched_cache.keys().cloned().collect::<Vec<_>>(), expected);
+}
@@ -5,21 +5,27 @@
 
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 use opentelemetry::KeyValue;
 use pyo3::exceptions::PyTypeError;
+use pyo3::exceptions::PyValueError;
 use pyo3::intern;
 use pyo3::prelude::*;
 use timely::dataflow::channels::pact::Pipeline;
 use timely::dataflow::operators::generic::builder_rc::OperatorBuilder;
 use timely::dataflow::operators::Map;
-use timely::dataflow::operators::Operator;
 use timely::dataflow::Scope;
 use timely::dataflow::Stream;
 use timely::progress::Timestamp;
 
 use crate::errors::tracked_err;
 use crate::errors::PythonException;
+use crate::metrics::bytes_like_len;
+use crate::metrics::item_size_metric_enabled;
 use crate::operators::ExtractKeyOp;
 use crate::pyo3_extensions::TdPyAny;
 use crate::pyo3_extensions::TdPyCallable;
@@ -27,6 +33,7 @@ use crate::recovery::*;
 use crate::timely::*;
 use crate::unwrap_any;
 use crate::with_timer;
+use crate::with_watchdog;
 
 /// Represents a `bytewax.outputs.Sink` from Python.
 #[derive(Clone)]
@@ -68,6 +75,68 @@ impl Sink {
     }
 }
 
+/// Whether an out-of-range `assign_all` worker index should be
+/// clamped into range (with a warning) instead of raising.
+///
+/// Defaults to raising, since silently clamping hides what's likely
+/// a bug in a custom `assign_all`. Set
+/// `BYTEWAX_ASSIGN_ALL_OUT_OF_RANGE=clamp` to opt into the more
+/// forgiving behavior instead. Checked once and cached, since this
+/// isn't something you'd toggle mid-run.
+fn clamp_out_of_range_worker() -> bool {
+    static CLAMP: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *CLAMP.get_or_init(|| {
+        std::env::var("BYTEWAX_ASSIGN_ALL_OUT_OF_RANGE")
+            .map(|v| v.eq_ignore_ascii_case("clamp"))
+            .unwrap_or(false)
+    })
+}
+
+/// Whether an empty `FixedPartitionedSink.list_parts` on a worker
+/// should raise instead of just logging a warning.
+///
+/// Defaults to warning, since a worker legitimately having no local
+/// partitions is normal for many `list_parts` implementations (e.g.
+/// only worker 0 owns any partitions). Set
+/// `BYTEWAX_EMPTY_LIST_PARTS=error` to raise instead, e.g. to catch a
+/// misconfigured sink in CI. Checked once and cached, since this
+/// isn't something you'd toggle mid-run.
+fn error_on_empty_list_parts() -> bool {
+    static ERROR: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ERROR.get_or_init(|| {
+        std::env::var("BYTEWAX_EMPTY_LIST_PARTS")
+            .map(|v| v.eq_ignore_ascii_case("error"))
+            .unwrap_or(false)
+    })
+}
+
+/// Validate a worker index returned by
+/// `FixedPartitionedSink.assign_all` against the actual worker count.
+fn checked_worker_index(
+    part: StateKey,
+    worker: usize,
+    worker_count: WorkerCount,
+) -> PyResult<(StateKey, WorkerIndex)> {
+    if worker < worker_count.0 {
+        Ok((part, WorkerIndex(worker)))
+    } else if clamp_out_of_range_worker() {
+        let clamped = worker % worker_count.0;
+        tracing::warn!(
+            "`FixedPartitionedSink.assign_all` assigned partition {part:?} to out-of-range \
+            worker index {worker} (only {} workers); clamping to worker {clamped}",
+            worker_count.0
+        );
+        Ok((part, WorkerIndex(clamped)))
+    } else {
+        Err(tracked_err::<PyValueError>(&format!(
+            "`FixedPartitionedSink.assign_all` assigned partition {part:?} to out-of-range \
+            worker index {worker}; only {} workers are running. Set \
+            `BYTEWAX_ASSIGN_ALL_OUT_OF_RANGE=clamp` to clamp instead of erroring",
+            worker_count.0
+        )))
+    }
+}
+
 /// Represents a `bytewax.outputs.PartitionedOutput` from Python.
 #[derive(Clone)]
 pub(crate) struct FixedPartitionedSink(Py<PyAny>);
@@ -110,15 +179,114 @@ impl FixedPartitionedSink {
             .extract(py)
     }
 
-    fn build_part_assigner(&self, py: Python) -> PyResult<PartitionAssigner> {
-        Ok(PartitionAssigner(
-            self.0.getattr(py, "part_fn")?.extract(py)?,
-        ))
+    fn build_part_assigner(&self, py: Python, step_id: &StepId) -> PyResult<PartAssigner> {
+        if self.explicit_partitioning(py)? {
+            Ok(PartAssigner::Explicit(ExplicitPartitionAssigner(
+                step_id.clone(),
+            )))
+        } else {
+            Ok(PartAssigner::Hashed(PartitionAssigner(
+                self.0.getattr(py, "part_fn")?.extract(py)?,
+            )))
+        }
+    }
+
+    /// Whether upstream `(key, value)` pairs' keys are themselves
+    /// partition names, bypassing `part_fn`-based hashing entirely.
+    fn explicit_partitioning(&self, py: Python) -> PyResult<bool> {
+        self.0.call_method0(py, "explicit_partitioning")?.extract(py)
+    }
+
+    /// Ask the sink to pin partitions to workers up front, bypassing
+    /// per-key `part_fn`-based assignment.
+    ///
+    /// `FixedPartitionedSink.assign_all` hands back raw worker
+    /// indices with no bounds checking of its own, unlike `part_fn`
+    /// hashes (which `partition_fanout` always wraps modulo the
+    /// known partition count). A custom `assign_all` that returns an
+    /// index `>= worker_count` would otherwise pin a partition to a
+    /// worker that doesn't exist, silently dropping it downstream.
+    /// Catch that here instead.
+    fn assign_all(
+        &self,
+        py: Python,
+        parts: Vec<StateKey>,
+        worker_count: WorkerCount,
+    ) -> PyResult<Option<BTreeMap<StateKey, WorkerIndex>>> {
+        let assignment: Option<BTreeMap<StateKey, usize>> = self
+            .0
+            .call_method1(py, "assign_all", (parts, worker_count.0))?
+            .extract(py)?;
+        assignment
+            .map(|assignment| {
+                assignment
+                    .into_iter()
+                    .map(|(part, worker)| checked_worker_index(part, worker, worker_count))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    /// Whether to tick the clock even on epochs with no items for
+    /// this sink.
+    fn emit_heartbeats(&self, py: Python) -> PyResult<bool> {
+        self.0.call_method0(py, "emit_heartbeats")?.extract(py)
+    }
+
+    /// Cap on how many partitions this worker builds at once.
+    fn max_open_parts(&self, py: Python) -> PyResult<Option<usize>> {
+        self.0.call_method0(py, "max_open_parts")?.extract(py)
+    }
+
+    /// How many partition snapshots to accumulate before flushing
+    /// them downstream mid-epoch.
+    fn snapshot_flush_threshold(&self, py: Python) -> PyResult<Option<usize>> {
+        self.0
+            .call_method0(py, "snapshot_flush_threshold")?
+            .extract(py)
+    }
+
+    /// Cap on how many items are handed to a single `write_batch`
+    /// call.
+    fn max_write_batch(&self, py: Python) -> PyResult<Option<usize>> {
+        self.0.call_method0(py, "max_write_batch")?.extract(py)
+    }
+
+    /// Whether writes to this partition should currently be paused.
+    fn is_paused(&self, py: Python, for_part: &StateKey) -> PyResult<bool> {
+        self.0
+            .call_method1(py, "is_paused", (for_part.clone(),))?
+            .extract(py)
     }
 }
 
+/// Wrap a partition's own snapshot together with any items buffered
+/// while `FixedPartitionedSink.is_paused` returned `True`, so a
+/// pause backlog survives a restart.
+///
+/// Kept as an opaque `(pending, state)` tuple, rather than mixing
+/// into the user's own state format, so this stays invisible to
+/// `FixedPartitionedSink` implementations that don't use `is_paused`.
+fn wrap_paused_state(py: Python, pending: Vec<PyObject>, state: TdPyAny) -> TdPyAny {
+    let state: PyObject = state.into();
+    TdPyAny::from(IntoPy::<PyObject>::into_py((pending, state), py))
+}
+
+/// Inverse of [`wrap_paused_state`].
+fn unwrap_paused_state(py: Python, state: TdPyAny) -> PyResult<(Vec<PyObject>, TdPyAny)> {
+    let state: PyObject = state.into();
+    let (pending, inner): (Vec<PyObject>, PyObject) = state.extract(py)?;
+    Ok((pending, TdPyAny::from(inner)))
+}
+
 /// Represents a `bytewax.outputs.StatefulSinkPartition` in Python.
-struct StatefulPartition(Py<PyAny>);
+struct StatefulPartition {
+    ob: Py<PyAny>,
+    /// Whether the concrete `write_batch` override also accepts the
+    /// epoch, for backward compatibility with subclasses written
+    /// against the old `write_batch(self, values)` signature.
+    write_batch_wants_epoch: bool,
+}
 
 /// Do some eager type checking.
 impl<'py> FromPyObject<'py> for StatefulPartition {
@@ -132,25 +300,56 @@ impl<'py> FromPyObject<'py> for StatefulPartition {
                 "stateful sink partition must subclass `bytewax.outputs.StatefulSinkPartition`",
             ))
         } else {
-            Ok(Self(ob.to_object(py)))
+            let write_batch = ob.getattr("write_batch")?;
+            let params = py
+                .import_bound("inspect")?
+                .call_method1("signature", (write_batch,))?
+                .getattr("parameters")?;
+            let write_batch_wants_epoch = params.len()? >= 2;
+            Ok(Self {
+                ob: ob.to_object(py),
+                write_batch_wants_epoch,
+            })
         }
     }
 }
 
 impl StatefulPartition {
-    fn write_batch(&self, py: Python, values: Vec<PyObject>) -> PyResult<()> {
-        let _ = self
-            .0
-            .call_method1(py, intern!(py, "write_batch"), (values,))?;
-        Ok(())
+    /// Returns whatever `write_batch` reported it committed, or an
+    /// empty `Vec` if it returned `None` (the default).
+    fn write_batch(
+        &self,
+        py: Python,
+        values: Vec<PyObject>,
+        epoch: u64,
+    ) -> PyResult<Vec<PyObject>> {
+        let ret = if self.write_batch_wants_epoch {
+            self.ob
+                .call_method1(py, intern!(py, "write_batch"), (values, epoch))?
+        } else {
+            self.ob
+                .call_method1(py, intern!(py, "write_batch"), (values,))?
+        };
+        ret.extract::<Option<Vec<PyObject>>>(py)
+            .map(|committed| committed.unwrap_or_default())
     }
 
     fn snapshot(&self, py: Python) -> PyResult<TdPyAny> {
-        Ok(self.0.call_method0(py, intern!(py, "snapshot"))?.into())
+        Ok(self.ob.call_method0(py, intern!(py, "snapshot"))?.into())
     }
 
     fn close(&self, py: Python) -> PyResult<()> {
-        let _ = self.0.call_method0(py, "close")?;
+        let _ = self.ob.call_method0(py, "close")?;
+        Ok(())
+    }
+
+    fn on_primary_acquired(&self, py: Python) -> PyResult<()> {
+        let _ = self.ob.call_method0(py, "on_primary_acquired")?;
+        Ok(())
+    }
+
+    fn on_primary_lost(&self, py: Python) -> PyResult<()> {
+        let _ = self.ob.call_method0(py, "on_primary_lost")?;
         Ok(())
     }
 }
@@ -163,18 +362,169 @@ impl Drop for StatefulPartition {
     }
 }
 
+/// This worker's open partitions for a `partitioned_output` step,
+/// bounded by `FixedPartitionedSink.max_open_parts`.
+///
+/// Once the number of open partitions would exceed the cap, the
+/// least-recently-written one is evicted: it must be snapshotted and
+/// closed by the caller, then [`Self::note_evicted`] called so it
+/// can be rebuilt from that snapshot on its next write rather than
+/// from scratch.
+struct OutputParts {
+    parts: BTreeMap<StateKey, StatefulPartition>,
+    /// Partition keys in least-to-most-recently-written order.
+    lru: VecDeque<StateKey>,
+    /// Snapshots of partitions evicted for being over `cap`.
+    evicted_state: BTreeMap<StateKey, TdPyAny>,
+    /// Items buffered for a partition while `FixedPartitionedSink.is_paused`
+    /// reported it paused, awaiting either a flush (once resumed) or
+    /// inclusion in a snapshot (to survive a restart while still
+    /// paused).
+    pending: BTreeMap<StateKey, Vec<PyObject>>,
+    cap: Option<usize>,
+}
+
+impl OutputParts {
+    fn new(cap: Option<usize>) -> Self {
+        Self {
+            parts: BTreeMap::new(),
+            lru: VecDeque::new(),
+            evicted_state: BTreeMap::new(),
+            pending: BTreeMap::new(),
+            cap,
+        }
+    }
+
+    /// Get this partition, building it anew or rebuilding it from an
+    /// evicted snapshot if it's not currently open, and mark it as
+    /// just-written.
+    ///
+    /// Fires `on_primary_acquired` the first time this partition is
+    /// ever opened on this worker, but not when it's merely rebuilt
+    /// here after an eviction for being over `cap`, since this
+    /// worker never stopped being primary in that case.
+    fn get_or_build(
+        &mut self,
+        py: Python,
+        sink: &FixedPartitionedSink,
+        step_id: &StepId,
+        part_key: &StateKey,
+    ) -> PyResult<&StatefulPartition> {
+        if !self.parts.contains_key(part_key) {
+            let evicted = self.evicted_state.remove(part_key);
+            let is_new = evicted.is_none();
+            let mut resume_state = None;
+            if let Some(state) = evicted {
+                let (pending, inner) = unwrap_paused_state(py, state)?;
+                if !pending.is_empty() {
+                    self.pending.insert(part_key.clone(), pending);
+                }
+                resume_state = Some(inner.into());
+            }
+            let part = sink.build_part(py, step_id, part_key, resume_state)?;
+            if is_new {
+                part.on_primary_acquired(py)?;
+            }
+            self.parts.insert(part_key.clone(), part);
+        } else {
+            self.lru.retain(|k| k != part_key);
+        }
+        self.lru.push_back(part_key.clone());
+        Ok(self.parts.get(part_key).unwrap())
+    }
+
+    /// Take any items buffered for `part_key` while it was paused, to
+    /// flush ahead of new items now that it's resumed.
+    fn take_pending(&mut self, part_key: &StateKey) -> Vec<PyObject> {
+        self.pending.remove(part_key).unwrap_or_default()
+    }
+
+    /// Buffer items for `part_key` while `FixedPartitionedSink.is_paused`
+    /// reports it paused, instead of handing them to `write_batch`.
+    fn buffer_pending(&mut self, part_key: StateKey, mut items: Vec<PyObject>) {
+        self.pending.entry(part_key).or_default().append(&mut items);
+    }
+
+    /// Items currently buffered for `part_key`, to fold into its next
+    /// snapshot. Empty if it isn't paused or has nothing buffered.
+    fn pending_for(&self, part_key: &StateKey) -> Vec<PyObject> {
+        self.pending.get(part_key).cloned().unwrap_or_default()
+    }
+
+    /// Restore a paused backlog for a partition just resumed from a
+    /// recovery load.
+    fn restore_pending(&mut self, part_key: StateKey, items: Vec<PyObject>) {
+        if !items.is_empty() {
+            self.pending.insert(part_key, items);
+        }
+    }
+
+    /// Partition keys over `cap`, least-recently-written first.
+    ///
+    /// The caller must snapshot and [`Self::remove`] each one; this
+    /// doesn't remove them itself since snapshotting can fail.
+    fn over_cap(&self) -> impl Iterator<Item = StateKey> + '_ {
+        let over_by = self
+            .cap
+            .map_or(0, |cap| self.parts.len().saturating_sub(cap));
+        self.lru.iter().take(over_by).cloned()
+    }
+
+    /// Remove and return a partition, e.g. to close it after
+    /// snapshotting it for eviction.
+    ///
+    /// Also drops any buffered pause backlog; the caller must have
+    /// already folded it into whatever snapshot it's taking, if any,
+    /// via [`Self::pending_for`].
+    fn remove(&mut self, part_key: &StateKey) -> Option<StatefulPartition> {
+        self.lru.retain(|k| k != part_key);
+        self.pending.remove(part_key);
+        self.parts.remove(part_key)
+    }
+
+    fn get(&self, part_key: &StateKey) -> Option<&StatefulPartition> {
+        self.parts.get(part_key)
+    }
+
+    /// Insert a partition resumed from a recovery load, marking it
+    /// as just-written.
+    fn insert(&mut self, part_key: StateKey, part: StatefulPartition) {
+        self.lru.retain(|k| k != &part_key);
+        self.lru.push_back(part_key.clone());
+        self.parts.insert(part_key, part);
+    }
+
+    /// Record that `part_key` was evicted with this snapshot state,
+    /// so [`Self::get_or_build`] can resume it from here.
+    fn note_evicted(&mut self, part_key: StateKey, state: TdPyAny) {
+        self.evicted_state.insert(part_key, state);
+    }
+
+    fn len(&self) -> usize {
+        self.parts.len()
+    }
+}
+
 /// This is a separate object than the bundle so we can use Python's
 /// RC to clone it into the exchange closure.
 struct PartitionAssigner(TdPyCallable);
 
 impl PartitionAssigner {
-    fn part_fn(&self, py: Python, key: &StateKey) -> PyResult<usize> {
-        self.0.bind(py).call1((key.clone(),))?.extract()
+    /// `part_fn` may return either a single hash value, for the
+    /// common case of one partition per key, or a list of hash
+    /// values to fan an item out to several partitions at once.
+    fn part_fn(&self, py: Python, key: &StateKey) -> PyResult<Vec<usize>> {
+        let ret = self.0.bind(py).call1((key.clone(),))?;
+        if let Ok(single) = ret.extract::<usize>() {
+            Ok(vec![single])
+        } else {
+            ret.extract::<Vec<usize>>()
+        }
     }
 }
 
-impl PartitionFn<StateKey> for PartitionAssigner {
-    fn assign(&self, key: &StateKey) -> usize {
+impl FanoutPartitionFn<StateKey> for PartitionAssigner {
+    fn assign(&self, key: &StateKey, _known: &BTreeSet<StateKey>) -> Vec<usize> {
         // TODO: This is a hot inner GIL acquisition. This should be
         // refactored into the output operator itself, but because
         // we're piggy-backing on the pure-Timely recovery partitioned
@@ -186,6 +536,47 @@ impl PartitionFn<StateKey> for PartitionAssigner {
     }
 }
 
+/// Routes an item directly to the partition named by its own key,
+/// bypassing `FixedPartitionedSink.part_fn` entirely.
+///
+/// Used when `FixedPartitionedSink.explicit_partitioning` is `True`:
+/// the upstream `(key, value)` pair's key is itself one of
+/// `list_parts`'s partition names, for records that already know
+/// which partition they belong to, rather than one that needs
+/// hashing to be assigned to one.
+struct ExplicitPartitionAssigner(StepId);
+
+impl FanoutPartitionFn<StateKey> for ExplicitPartitionAssigner {
+    fn assign(&self, key: &StateKey, known: &BTreeSet<StateKey>) -> Vec<usize> {
+        match known.iter().position(|part| part == key) {
+            Some(idx) => vec![idx],
+            None => unwrap_any!(Err(tracked_err::<PyValueError>(&format!(
+                "step {} requires `(partition_key, value)` 2-tuples from upstream when \
+                `FixedPartitionedSink.explicit_partitioning` is `True`; key {key} does not \
+                match any partition name returned by `list_parts`",
+                self.0
+            )))),
+        }
+    }
+}
+
+/// Either hash a key into a partition via `FixedPartitionedSink.part_fn`,
+/// or use it directly as a partition name, depending on
+/// `FixedPartitionedSink.explicit_partitioning`.
+enum PartAssigner {
+    Hashed(PartitionAssigner),
+    Explicit(ExplicitPartitionAssigner),
+}
+
+impl FanoutPartitionFn<StateKey> for PartAssigner {
+    fn assign(&self, key: &StateKey, known: &BTreeSet<StateKey>) -> Vec<usize> {
+        match self {
+            Self::Hashed(pf) => pf.assign(key, known),
+            Self::Explicit(pf) => pf.assign(key, known),
+        }
+    }
+}
+
 pub(crate) trait PartitionedOutputOp<S>
 where
     S: Scope,
@@ -197,13 +588,17 @@ where
     ///
     /// This can't be unified into the recovery system output
     /// operators because they are stateless.
+    ///
+    /// The third stream carries `(part_key, epoch, item)` for every
+    /// item a partition's `write_batch` reported it committed. It is
+    /// empty for partitions whose `write_batch` returns `None`.
     fn partitioned_output(
         &self,
         py: Python,
         step_id: StepId,
         sink: FixedPartitionedSink,
         loads: &Stream<S, Snapshot>,
-    ) -> PyResult<(ClockStream<S>, Stream<S, Snapshot>)>;
+    ) -> PyResult<(ClockStream<S>, Stream<S, Snapshot>, Stream<S, TdPyAny>)>;
 }
 
 impl<S> PartitionedOutputOp<S> for Stream<S, TdPyAny>
@@ -216,17 +611,53 @@ where
         step_id: StepId,
         sink: FixedPartitionedSink,
         loads: &Stream<S, Snapshot>,
-    ) -> PyResult<(ClockStream<S>, Stream<S, Snapshot>)> {
+    ) -> PyResult<(ClockStream<S>, Stream<S, Snapshot>, Stream<S, TdPyAny>)> {
         let this_worker = self.scope().w_index();
 
+        let heartbeat = sink
+            .emit_heartbeats(py)
+            .reraise("error calling `FixedPartitionedSink.emit_heartbeats`")?;
+        let max_open_parts = sink
+            .max_open_parts(py)
+            .reraise("error calling `FixedPartitionedSink.max_open_parts`")?;
+        let snapshot_flush_threshold = sink
+            .snapshot_flush_threshold(py)
+            .reraise("error calling `FixedPartitionedSink.snapshot_flush_threshold`")?;
+        let max_write_batch = sink
+            .max_write_batch(py)
+            .reraise("error calling `FixedPartitionedSink.max_write_batch`")?;
+
         let local_parts = sink.list_parts(py).reraise("error listing partitions")?;
+        if local_parts.is_empty() {
+            let msg = format!(
+                "step {step_id} `FixedPartitionedSink.list_parts` returned no partitions \
+                on worker {this_worker:?}"
+            );
+            if error_on_empty_list_parts() {
+                return Err(tracked_err::<PyValueError>(&msg));
+            }
+            tracing::warn!(
+                "{msg}; if no worker owns any partition, this step will silently write \
+                nothing. Set `BYTEWAX_EMPTY_LIST_PARTS=error` to raise instead"
+            );
+        }
         let all_parts = local_parts.into_broadcast(&self.scope(), S::Timestamp::minimum());
-        let primary_updates = all_parts.assign_primaries(format!("{step_id}.assign_primaries"));
+        let worker_count = self.scope().w_count();
+        let assign_all_sink = sink.clone();
+        let overrides: Option<Box<dyn Fn(&[StateKey]) -> BTreeMap<StateKey, WorkerIndex>>> =
+            Some(Box::new(move |parts: &[StateKey]| {
+                unwrap_any!(Python::with_gil(|py| assign_all_sink
+                    .assign_all(py, parts.to_vec(), worker_count)
+                    .reraise("error calling `FixedPartitionedSink.assign_all`")))
+                .unwrap_or_default()
+            }));
+        let primary_updates =
+            all_parts.assign_primaries(format!("{step_id}.assign_primaries"), overrides);
 
-        let pf = sink.build_part_assigner(py)?;
+        let pf = sink.build_part_assigner(py, &step_id)?;
         let routed_self = self
             .extract_key(step_id.clone())
-            .partition(
+            .partition_fanout(
                 format!("{step_id}.partition"),
                 &all_parts.map(|(part, _worker)| part),
                 pf,
@@ -246,12 +677,20 @@ where
 
         let (mut clock_output, clock) = op_builder.new_output();
         let (mut snaps_output, snaps) = op_builder.new_output();
+        let (mut confirm_output, confirmations) = op_builder.new_output();
 
         let meter = opentelemetry::global::meter("bytewax");
         let item_inp_count = meter
             .u64_counter("item_inp_count")
             .with_description("number of items this step has ingested")
             .init();
+        let item_inp_bytes = meter
+            .u64_counter("item_inp_bytes")
+            .with_description(
+                "total byte length of ingested items that are `bytes` or \
+                `bytearray`; only recorded when BYTEWAX_ITEM_SIZE_METRIC=true",
+            )
+            .init();
         let write_batch_histogram = meter
             .f64_histogram("out_part_write_batch_duration_seconds")
             .with_description("`write_batch` duration in seconds")
@@ -260,13 +699,83 @@ where
             .f64_histogram("snapshot_duration_seconds")
             .with_description("`snapshot` duration in seconds")
             .init();
+        let snapshots_written = meter
+            .u64_counter("snapshots_written")
+            .with_description("number of partition snapshots written per epoch")
+            .init();
+        let gil_wait_histogram = meter
+            .f64_histogram("gil_wait_duration_seconds")
+            .with_description("time spent waiting to acquire the GIL")
+            .init();
+        let epochs_closed = meter
+            .u64_counter("epochs_closed")
+            .with_description("number of epochs this step has closed")
+            .init();
+        let callback_timeout_counter = meter
+            .u64_counter("callback_timeout")
+            .with_description("number of times a callback ran longer than BYTEWAX_CALLBACK_TIMEOUT_SECONDS")
+            .init();
         let labels = vec![
             KeyValue::new("step_id", step_id.0.to_string()),
             KeyValue::new("worker_index", this_worker.0.to_string()),
         ];
+        let owned_part_count = Arc::new(AtomicU64::new(0));
+        {
+            let owned_part_count = Arc::clone(&owned_part_count);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("out_owned_part_count")
+                .with_description("number of partitions this worker currently owns")
+                .with_callback(move |observer| {
+                    observer.observe(owned_part_count.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
+        let frontier_epoch = Arc::new(AtomicU64::new(0));
+        {
+            let frontier_epoch = Arc::clone(&frontier_epoch);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("operator_frontier_epoch")
+                .with_description("input frontier epoch of this operator, for lag monitoring")
+                .with_callback(move |observer| {
+                    observer.observe(frontier_epoch.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
+        let pending_epochs = Arc::new(AtomicU64::new(0));
+        let pending_items = Arc::new(AtomicU64::new(0));
+        {
+            let pending_epochs = Arc::clone(&pending_epochs);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("notificator_pending_epochs")
+                .with_description(
+                    "number of distinct epochs of input buffered awaiting the \
+                    frontier to advance, for detecting a step falling behind",
+                )
+                .with_callback(move |observer| {
+                    observer.observe(pending_epochs.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
+        {
+            let pending_items = Arc::clone(&pending_items);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("notificator_pending_items")
+                .with_description(
+                    "total number of items buffered across all pending epochs, \
+                    for detecting a step falling behind",
+                )
+                .with_callback(move |observer| {
+                    observer.observe(pending_items.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
 
         op_builder.build(move |init_caps| {
-            let parts: BTreeMap<StateKey, StatefulPartition> = BTreeMap::new();
+            let parts = OutputParts::new(max_open_parts);
             // Which partitions were written to in this epoch. We only
             // snapshot those.
             let awoken: BTreeSet<StateKey> = BTreeSet::new();
@@ -280,7 +789,17 @@ where
             let mut ncater = EagerNotificator::new(init_caps, (parts, awoken));
 
             move |input_frontiers| {
-                tracing::debug_span!("operator", operator = op_name).in_scope(|| {
+                let span = tracing::debug_span!(
+                    "operator",
+                    operator = op_name,
+                    epoch = tracing::field::Empty,
+                    item_count = tracing::field::Empty,
+                );
+                span.in_scope(|| {
+                    if let Some(epoch) = input_frontiers.simplify() {
+                        frontier_epoch.store(*epoch, Ordering::Relaxed);
+                    }
+
                     routed_input.for_each(|cap, incoming| {
                         let epoch = cap.time();
                         assert!(routed_tmp.is_empty());
@@ -299,42 +818,157 @@ where
                     });
                     loads_input.buffer_notify(&mut loads_inbuf, &mut ncater);
 
+                    pending_epochs.store(items_inbuf.len() as u64, Ordering::Relaxed);
+                    pending_items.store(
+                        items_inbuf
+                            .values()
+                            .flat_map(|part_to_items| part_to_items.values())
+                            .map(Vec::len)
+                            .sum::<usize>() as u64,
+                        Ordering::Relaxed,
+                    );
+
+                    if heartbeat {
+                        if let Some(epoch) = input_frontiers.simplify() {
+                            ncater.notify_at(epoch);
+                        }
+                    }
+
                     ncater.for_each(
                         input_frontiers,
                         |caps, (parts, awoken)| {
                             let clock_cap = &caps[0];
+                            let snaps_cap = &caps[1];
+                            let confirm_cap = &caps[2];
                             let epoch = clock_cap.time();
 
+                            let mut confirm_handle = confirm_output.activate();
+                            let mut confirm_session = confirm_handle.session(confirm_cap);
+
                             // Writing happens eagerly in each epoch. We
                             // still use a notificator at all because we
                             // need to ensure that writes happen in epoch
                             // order.
                             if let Some(part_to_items) = items_inbuf.remove(epoch) {
-                                Python::with_gil(|py| {
+                                span.record("epoch", tracing::field::debug(epoch));
+                                let mut total_items = 0usize;
+                                with_gil_timed!(gil_wait_histogram, labels, |py| {
                                     for (part_key, items) in part_to_items {
-                                        let part = parts
-                                            .entry(part_key.clone())
-                                            // If there's no resume data for
-                                            // this partition, lazily create
-                                            // it.
-                                            .or_insert_with_key(|part_key| {
-                                                unwrap_any!(sink
-                                                    .build_part(py, &step_id, part_key, None)
-                                                    .reraise("error init StatefulSink"))
-                                            });
-
-                                        let batch: Vec<_> =
+                                        // Make sure the partition is open
+                                        // (and any evicted pause backlog
+                                        // restored into `parts.pending`)
+                                        // even if we're about to buffer
+                                        // rather than write, so a paused
+                                        // partition still gets rebuilt on
+                                        // demand.
+                                        unwrap_any!(parts
+                                            .get_or_build(py, &sink, &step_id, &part_key)
+                                            .reraise("error init StatefulSink"));
+
+                                        let mut batch: Vec<TdPyAny> =
                                             items.into_iter().map(|(_k, v)| v.into()).collect();
                                         item_inp_count.add(batch.len() as u64, &labels);
-                                        with_timer!(
-                                            write_batch_histogram,
-                                            labels,
-                                            unwrap_any!(part.write_batch(py, batch))
-                                        );
+                                        if item_size_metric_enabled() {
+                                            let inp_bytes: u64 = batch
+                                                .iter()
+                                                .map(|item| bytes_like_len(py, item))
+                                                .sum();
+                                            item_inp_bytes.add(inp_bytes, &labels);
+                                        }
+                                        total_items += batch.len();
+
+                                        let paused = unwrap_any!(sink
+                                            .is_paused(py, &part_key)
+                                            .reraise("error calling `is_paused`"));
+                                        if paused {
+                                            parts.buffer_pending(
+                                                part_key.clone(),
+                                                batch.into_iter().map(|item| item.into()).collect(),
+                                            );
+                                            awoken.insert(part_key);
+                                            continue;
+                                        }
+
+                                        let mut pending: Vec<TdPyAny> = parts
+                                            .take_pending(&part_key)
+                                            .into_iter()
+                                            .map(|item| item.into())
+                                            .collect();
+                                        pending.append(&mut batch);
+                                        let batch = pending;
+                                        let part = parts.get(&part_key).unwrap();
+
+                                        // `max_write_batch` bounds the size
+                                        // of each `write_batch` call rather
+                                        // than the amount buffered per
+                                        // epoch, so a partition that
+                                        // accumulates a huge batch in one
+                                        // epoch doesn't overrun a
+                                        // downstream API's request-size
+                                        // limit.
+                                        let chunk_size =
+                                            max_write_batch.unwrap_or(batch.len()).max(1);
+                                        for chunk in batch.chunks(chunk_size) {
+                                            let committed = with_timer!(
+                                                write_batch_histogram,
+                                                labels,
+                                                with_watchdog!(
+                                                    callback_timeout_counter,
+                                                    labels,
+                                                    format!(
+                                                        "`write_batch` in step {step_id} for part {part_key}"
+                                                    ),
+                                                    unwrap_any!(part.write_batch(
+                                                        py,
+                                                        chunk.to_vec(),
+                                                        *epoch
+                                                    ))
+                                                )
+                                            );
+
+                                            for item in committed {
+                                                let confirm = IntoPy::<PyObject>::into_py(
+                                                    (part_key.clone(), *epoch, item),
+                                                    py,
+                                                );
+                                                confirm_session.give(TdPyAny::from(confirm));
+                                            }
+                                        }
 
                                         awoken.insert(part_key);
                                     }
+
+                                    // Bound how many partitions this
+                                    // worker holds open at once: snapshot
+                                    // and close the least-recently-written
+                                    // ones over the cap. They're rebuilt
+                                    // from that snapshot on next write.
+                                    let mut handle = snaps_output.activate();
+                                    let mut session = handle.session(snaps_cap);
+                                    for part_key in parts.over_cap().collect::<Vec<_>>() {
+                                        let pending = parts.pending_for(&part_key);
+                                        let part = parts.remove(&part_key).unwrap();
+                                        let state = with_timer!(
+                                            snapshot_histogram,
+                                            labels,
+                                            unwrap_any!(part
+                                                .snapshot(py)
+                                                .reraise("error snapshotting StatefulSink"))
+                                        );
+                                        let state = wrap_paused_state(py, pending, state);
+                                        drop(part);
+                                        awoken.remove(&part_key);
+                                        parts.note_evicted(part_key.clone(), state.clone());
+                                        session.give(Snapshot(
+                                            step_id.clone(),
+                                            part_key,
+                                            StateChange::Upsert(state),
+                                        ));
+                                        snapshots_written.add(1, &labels);
+                                    }
                                 });
+                                owned_part_count.store(parts.len() as u64, Ordering::Relaxed);
+                                span.record("item_count", total_items);
                             };
                         },
                         |caps, (parts, awoken)| {
@@ -342,6 +976,8 @@ where
                             let snaps_cap = &caps[1];
                             let epoch = clock_cap.time();
 
+                            epochs_closed.add(1, &labels);
+
                             clock_output.activate().session(clock_cap).give(());
 
                             // Always snapshot before building. If we have
@@ -356,18 +992,34 @@ where
                             // Make sure to only snapshot partitions
                             // that had data, otherwise we'll snapshot
                             // as loads are happening.
+                            let mut since_flush = 0usize;
                             while let Some(part_key) = awoken.pop_first() {
                                 let part = parts.get(&part_key).unwrap();
+                                let pending = parts.pending_for(&part_key);
                                 let state = with_timer!(
                                     snapshot_histogram,
                                     labels,
-                                    unwrap_any!(Python::with_gil(|py| part
-                                        .snapshot(py)
-                                        .reraise("error snapshotting StatefulSink")))
+                                    unwrap_any!(Python::with_gil(move |py| -> PyResult<TdPyAny> {
+                                        let state = part
+                                            .snapshot(py)
+                                            .reraise("error snapshotting StatefulSink")?;
+                                        Ok(wrap_paused_state(py, pending, state))
+                                    }))
                                 );
                                 let snap =
                                     Snapshot(step_id.clone(), part_key, StateChange::Upsert(state));
                                 session.give(snap);
+                                snapshots_written.add(1, &labels);
+
+                                // Flush what's buffered so far rather
+                                // than holding the whole epoch's
+                                // snapshots until this loop ends, if
+                                // configured.
+                                since_flush += 1;
+                                if snapshot_flush_threshold.is_some_and(|t| since_flush >= t) {
+                                    session = handle.session(snaps_cap);
+                                    since_flush = 0;
+                                }
                             }
 
                             // We must reset `awake` on each epoch.
@@ -380,25 +1032,35 @@ where
                                     if worker == this_worker {
                                         match change {
                                             StateChange::Upsert(state) => {
-                                                let part = unwrap_any!(Python::with_gil(|py| {
-                                                    sink.build_part(
-                                                        py,
-                                                        &step_id,
-                                                        &part_key,
-                                                        Some(state.into()),
-                                                    )
-                                                    .reraise("error resuming StatefulSink")
-                                                }));
-                                                parts.insert(part_key, part);
+                                                let (part, pending) =
+                                                    unwrap_any!(Python::with_gil(|py| {
+                                                        let (pending, state) =
+                                                            unwrap_paused_state(py, state)?;
+                                                        let part = sink
+                                                            .build_part(
+                                                                py,
+                                                                &step_id,
+                                                                &part_key,
+                                                                Some(state.into()),
+                                                            )
+                                                            .reraise("error resuming StatefulSink")?;
+                                                        part.on_primary_acquired(py)?;
+                                                        PyResult::Ok((part, pending))
+                                                    }));
+                                                parts.insert(part_key.clone(), part);
+                                                parts.restore_pending(part_key, pending);
                                             }
                                             StateChange::Discard => {
                                                 parts.remove(&part_key);
                                             }
                                         }
-                                    } else {
-                                        parts.remove(&part_key);
+                                    } else if let Some(part) = parts.remove(&part_key) {
+                                        unwrap_any!(Python::with_gil(|py| part
+                                            .on_primary_lost(py)
+                                            .reraise("error calling `on_primary_lost`")));
                                     }
                                 }
+                                owned_part_count.store(parts.len() as u64, Ordering::Relaxed);
                             }
                         },
                     );
@@ -406,7 +1068,7 @@ where
             }
         });
 
-        Ok((clock, snaps))
+        Ok((clock, snaps, confirmations))
     }
 }
 
@@ -441,6 +1103,73 @@ impl DynamicSink {
             .call_method1(py, "build", (step_id.clone(), index.0, count.0))?
             .extract(py)
     }
+
+    fn max_inflight_items(&self, py: Python) -> PyResult<Option<usize>> {
+        self.0.call_method0(py, "max_inflight_items")?.extract(py)
+    }
+
+    fn build_retry_backoff(&self, py: Python) -> PyResult<Option<(f64, u32)>> {
+        self.0.call_method0(py, "build_retry_backoff")?.extract(py)
+    }
+
+    fn lazy_build(&self, py: Python) -> PyResult<bool> {
+        self.0.call_method0(py, "lazy_build")?.extract(py)
+    }
+
+    fn is_idempotent(&self, py: Python) -> PyResult<bool> {
+        self.0.call_method0(py, "is_idempotent")?.extract(py)
+    }
+
+    /// Destination key to group `item` under before handing it to
+    /// `write_batch`, or `None` to leave it in the default group.
+    fn route_to(&self, py: Python, item: &PyObject) -> PyResult<Option<String>> {
+        self.0.call_method1(py, "route_to", (item,))?.extract(py)
+    }
+}
+
+/// Call `DynamicSink.build`, retrying with exponential backoff if
+/// `DynamicSink.build_retry_backoff` opts in, so a sink that talks
+/// to a flaky external system doesn't fail the whole dataflow on a
+/// single bad attempt.
+fn build_with_retry(
+    sink: DynamicSink,
+    py: Python,
+    step_id: &StepId,
+    index: WorkerIndex,
+    count: WorkerCount,
+) -> PyResult<StatelessPartition> {
+    let backoff = sink
+        .build_retry_backoff(py)
+        .reraise("error calling `DynamicSink.build_retry_backoff`")?;
+    let Some((base_delay_seconds, max_retries)) = backoff else {
+        return sink.build(py, step_id, index, count);
+    };
+
+    let mut attempt = 0;
+    loop {
+        match sink.clone().build(py, step_id, index, count) {
+            Ok(part) => return Ok(part),
+            Err(err) if attempt < max_retries => {
+                tracing::warn!(
+                    "error building `DynamicSink` in step {step_id} \
+                    (attempt {}/{max_retries}): {err}; retrying",
+                    attempt + 1,
+                );
+                let delay = base_delay_seconds * 2f64.powi(attempt as i32);
+                py.allow_threads(|| std::thread::sleep(std::time::Duration::from_secs_f64(delay)));
+                attempt += 1;
+            }
+            Err(err) => {
+                return Err(err).reraise_with(|| {
+                    format!(
+                        "error building `DynamicSink` in step {step_id} \
+                        after {} attempt(s)",
+                        attempt + 1,
+                    )
+                });
+            }
+        }
+    }
 }
 
 /// Represents a `bytewax.outputs.StatelessSinkPartition` in Python.
@@ -471,17 +1200,48 @@ impl StatelessPartition {
         Ok(())
     }
 
-    fn close(&self, py: Python) -> PyResult<()> {
-        let _ = self.0.call_method0(py, "close")?;
+    /// Like [`Self::write_batch`], but also passes the destination
+    /// key `DynamicSink.route_to` computed for every item in `items`.
+    fn write_batch_to(&self, py: Python, items: Vec<PyObject>, destination: &str) -> PyResult<()> {
+        let _ = self
+            .0
+            .call_method1(py, intern!(py, "write_batch"), (items, destination))?;
         Ok(())
     }
+
+    fn flush(&self, py: Python) -> PyResult<()> {
+        let _ = self.0.call_method0(py, intern!(py, "flush"))?;
+        Ok(())
+    }
+
+    fn on_epoch_complete(&self, py: Python, epoch: u64) -> PyResult<()> {
+        let _ = self
+            .0
+            .call_method1(py, intern!(py, "on_epoch_complete"), (epoch,))?;
+        Ok(())
+    }
+
+    /// Returns whatever `close` returned as a final batch to write, or
+    /// an empty `Vec` if it returned `None` (the default).
+    fn close(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let ret = self.0.call_method0(py, "close")?;
+        ret.extract::<Option<Vec<PyObject>>>(py)
+            .map(|batch| batch.unwrap_or_default())
+    }
 }
 
 impl Drop for StatelessPartition {
     fn drop(&mut self) {
-        unwrap_any!(Python::with_gil(|py| self
-            .close(py)
-            .reraise("error closing StatelessSinkPartition")));
+        unwrap_any!(Python::with_gil(|py| -> PyResult<()> {
+            let batch = self
+                .close(py)
+                .reraise("error closing StatelessSinkPartition")?;
+            if !batch.is_empty() {
+                self.write_batch(py, batch)
+                    .reraise("error writing final batch returned from `close`")?;
+            }
+            Ok(())
+        }));
     }
 }
 
@@ -493,12 +1253,23 @@ where
     ///
     /// Will manage automatically building sinks. All you have to do
     /// is pass in the definition.
+    ///
+    /// If `sink.is_idempotent()`, also tracks the high-water epoch
+    /// this worker has fully written and flushed, as a single small
+    /// recovery snapshot per worker. On resume, epochs at or below
+    /// that high-water mark are skipped instead of handed to
+    /// `write_batch` again, since we already know they made it out.
+    /// This only helps across a resume where the worker count is
+    /// unchanged; if it changes, the old high-water snapshot simply
+    /// won't be found and every epoch gets written, same as if
+    /// idempotency weren't declared.
     fn dynamic_output(
         &self,
         py: Python,
         step_id: StepId,
         sink: DynamicSink,
-    ) -> PyResult<ClockStream<S>>;
+        loads: &Stream<S, Snapshot>,
+    ) -> PyResult<(ClockStream<S>, Stream<S, Snapshot>)>;
 }
 
 impl<S> DynamicOutputOp<S> for Stream<S, TdPyAny>
@@ -510,62 +1281,289 @@ where
         py: Python,
         step_id: StepId,
         sink: DynamicSink,
-    ) -> PyResult<ClockStream<S>> {
+        loads: &Stream<S, Snapshot>,
+    ) -> PyResult<(ClockStream<S>, Stream<S, Snapshot>)> {
         let worker_index = self.scope().w_index();
         let worker_count = self.scope().w_count();
-        let mut part = Some(sink.build(py, &step_id, worker_index, worker_count)?);
+        let max_inflight_items = sink
+            .max_inflight_items(py)
+            .reraise("error calling `DynamicSink.max_inflight_items`")?;
+        let lazy_build = sink
+            .lazy_build(py)
+            .reraise("error calling `DynamicSink.lazy_build`")?;
+        let is_idempotent = sink
+            .is_idempotent(py)
+            .reraise("error calling `DynamicSink.is_idempotent`")?;
+        // Our high-water state is keyed by our own worker index and
+        // the worker count, so it's naturally sharded one key per
+        // worker with no need for a partition function, and a mark
+        // written under a different worker count never matches.
+        let high_water_key = StateKey(format!("{}/{}", worker_index.0, worker_count.0));
+
+        // If not lazy, build eagerly right now so a startup failure
+        // fails the dataflow immediately, as before. If lazy, defer
+        // to the first batch of items and stash the sink definition
+        // to build it with.
+        let mut part = if lazy_build {
+            None
+        } else {
+            Some(build_with_retry(
+                sink.clone(),
+                py,
+                &step_id,
+                worker_index,
+                worker_count,
+            )?)
+        };
+        let mut pending_sink = if lazy_build { Some(sink) } else { None };
 
         let meter = opentelemetry::global::meter("bytewax");
         let item_inp_count = meter
             .u64_counter("item_inp_count")
             .with_description("number of items this step has ingested")
             .init();
+        let item_inp_bytes = meter
+            .u64_counter("item_inp_bytes")
+            .with_description(
+                "total byte length of ingested items that are `bytes` or \
+                `bytearray`; only recorded when BYTEWAX_ITEM_SIZE_METRIC=true",
+            )
+            .init();
         let write_batch_histogram = meter
             .f64_histogram("out_part_write_batch_duration_seconds")
             .with_description("`write_batch` duration in seconds")
             .init();
+        let gil_wait_histogram = meter
+            .f64_histogram("gil_wait_duration_seconds")
+            .with_description("time spent waiting to acquire the GIL")
+            .init();
+        let epochs_closed = meter
+            .u64_counter("epochs_closed")
+            .with_description("number of epochs this step has closed")
+            .init();
+        let callback_timeout_counter = meter
+            .u64_counter("callback_timeout")
+            .with_description("number of times a callback ran longer than BYTEWAX_CALLBACK_TIMEOUT_SECONDS")
+            .init();
         let labels = vec![
             KeyValue::new("step_id", step_id.0.to_string()),
             KeyValue::new("worker_index", worker_index.0.to_string()),
         ];
+        let frontier_epoch = Arc::new(AtomicU64::new(0));
+        {
+            let frontier_epoch = Arc::clone(&frontier_epoch);
+            let labels = labels.clone();
+            meter
+                .u64_observable_gauge("operator_frontier_epoch")
+                .with_description("input frontier epoch of this operator, for lag monitoring")
+                .with_callback(move |observer| {
+                    observer.observe(frontier_epoch.load(Ordering::Relaxed), &labels);
+                })
+                .init();
+        }
 
-        let downstream = self.unary_frontier(Pipeline, &step_id.0, |_init_cap, _info| {
-            let mut tmp_incoming: Vec<TdPyAny> = Vec::new();
-
-            move |input, output| {
-                part = part.take().and_then(|sink| {
-                    input.for_each(|cap, incoming| {
-                        assert!(tmp_incoming.is_empty());
-                        incoming.swap(&mut tmp_incoming);
-
-                        let mut output_session = output.session(&cap);
-
-                        let batch: Vec<PyObject> = tmp_incoming
-                            .split_off(0)
-                            .into_iter()
-                            .map(|item| item.into())
-                            .collect();
-                        item_inp_count.add(batch.len() as u64, &labels);
-                        with_timer!(
-                            write_batch_histogram,
-                            &labels,
-                            unwrap_any!(Python::with_gil(|py| sink
-                                .write_batch(py, batch)
-                                .reraise("error writing output batch")))
-                        );
-
-                        output_session.give(());
-                    });
+        // Every worker already knows the whole assignment table
+        // without needing to ask Python or broadcast anything: key
+        // `i/<worker count>` is always primary on worker `i`. Loaded
+        // marks from an execution with a different worker count have
+        // no route here, so they're dropped before routing.
+        let primary_updates = (0..worker_count.0)
+            .map(move |i| (StateKey(format!("{i}/{}", worker_count.0)), WorkerIndex(i)))
+            .into_stream_at(&self.scope(), S::Timestamp::minimum());
+        let count_suffix = format!("/{}", worker_count.0);
+        let routed_loads = loads
+            .filter_snaps(step_id.clone())
+            .flat_map(move |(key, change)| key.0.ends_with(&count_suffix).then_some((key, change)))
+            .route(format!("{step_id}.loads_route"), &primary_updates);
+
+        let op_name = format!("{step_id}.dynamic_output");
+        let mut op_builder = OperatorBuilder::new(op_name.clone(), self.scope());
+
+        let mut items_input = op_builder.new_input(self, Pipeline);
+        let mut loads_input = op_builder.new_input(&routed_loads, routed_exchange());
 
-                    if input.frontier().is_empty() {
-                        None
-                    } else {
-                        Some(sink)
+        let (mut clock_output, clock) = op_builder.new_output();
+        let (mut snap_output, snap) = op_builder.new_output();
+
+        op_builder.build(move |init_caps| {
+            let mut high_water_epoch: Option<u64> = None;
+            let mut flushed_through: Option<S::Timestamp> = None;
+
+            let mut items_inbuf = InBuffer::new();
+            let mut loads_inbuf = InBuffer::new();
+            let mut ncater = EagerNotificator::new(init_caps, ());
+
+            move |input_frontiers| {
+                let span = tracing::debug_span!(
+                    "operator",
+                    operator = op_name,
+                    epoch = tracing::field::Empty,
+                    item_count = tracing::field::Empty,
+                );
+                span.in_scope(|| {
+                    if let Some(epoch) = input_frontiers.simplify() {
+                        frontier_epoch.store(*epoch, Ordering::Relaxed);
                     }
+
+                    items_input.buffer_notify(&mut items_inbuf, &mut ncater);
+                    loads_input.buffer_notify(&mut loads_inbuf, &mut ncater);
+
+                    ncater.for_each(
+                        input_frontiers,
+                        |caps, ()| {
+                            let clock_cap = &caps[0];
+                            let epoch = clock_cap.time();
+
+                            if let Some(items) = items_inbuf.remove(epoch) {
+                                if part.is_none() {
+                                    if let Some(sink) = pending_sink.take() {
+                                        part = Some(unwrap_any!(Python::with_gil(|py| {
+                                            build_with_retry(
+                                                sink,
+                                                py,
+                                                &step_id,
+                                                worker_index,
+                                                worker_count,
+                                            )
+                                        })));
+                                    }
+                                }
+                                let sink = part.as_ref().expect("DynamicSink built above");
+
+                                let already_written = is_idempotent
+                                    && high_water_epoch.map(|hw| *epoch <= hw).unwrap_or(false);
+
+                                span.record("epoch", tracing::field::debug(epoch));
+                                span.record("item_count", items.len());
+
+                                if already_written {
+                                    tracing::debug!(
+                                        "epoch {epoch} already confirmed written by \
+                                        idempotent DynamicSink before resume; skipping"
+                                    );
+                                } else {
+                                    let batch: Vec<PyObject> =
+                                        items.into_iter().map(|item| item.into()).collect();
+                                    item_inp_count.add(batch.len() as u64, &labels);
+                                    if item_size_metric_enabled() {
+                                        let inp_bytes: u64 = Python::with_gil(|py| {
+                                            batch.iter().map(|item| bytes_like_len(py, item)).sum()
+                                        });
+                                        item_inp_bytes.add(inp_bytes, &labels);
+                                    }
+                                    // Group items by the destination
+                                    // `DynamicSink.route_to` computes
+                                    // for them, if any, so a single
+                                    // dynamic sink can fan out to
+                                    // several named destinations it
+                                    // manages internally.
+                                    let mut groups: BTreeMap<Option<String>, Vec<PyObject>> =
+                                        BTreeMap::new();
+                                    unwrap_any!(with_gil_timed!(
+                                        gil_wait_histogram,
+                                        labels,
+                                        |py| -> PyResult<()> {
+                                            for item in batch {
+                                                let destination = sink
+                                                    .route_to(py, &item)
+                                                    .reraise("error calling `route_to`")?;
+                                                groups.entry(destination).or_default().push(item);
+                                            }
+                                            Ok(())
+                                        }
+                                    ));
+                                    // `max_inflight_items` bounds the
+                                    // size of each `write_batch` call
+                                    // rather than pausing ingestion,
+                                    // since items are now buffered a
+                                    // whole epoch at a time to know
+                                    // when it's safe to flush and
+                                    // snapshot.
+                                    for (destination, group) in groups {
+                                        let chunk_size =
+                                            max_inflight_items.unwrap_or(group.len()).max(1);
+                                        for chunk in group.chunks(chunk_size) {
+                                            with_timer!(
+                                                write_batch_histogram,
+                                                &labels,
+                                                with_watchdog!(
+                                                    callback_timeout_counter,
+                                                    &labels,
+                                                    format!("`write_batch` in step {step_id}"),
+                                                    unwrap_any!(Python::with_gil(|py| {
+                                                        match &destination {
+                                                            Some(destination) => sink
+                                                                .write_batch_to(
+                                                                    py,
+                                                                    chunk.to_vec(),
+                                                                    destination,
+                                                                ),
+                                                            None => sink
+                                                                .write_batch(py, chunk.to_vec()),
+                                                        }
+                                                        .reraise("error writing output batch")
+                                                    }))
+                                                )
+                                            );
+                                        }
+                                    }
+                                }
+
+                                clock_output.activate().session(clock_cap).give(());
+                            }
+                        },
+                        |caps, ()| {
+                            let clock_cap = &caps[0];
+                            let snap_cap = &caps[1];
+                            let epoch = clock_cap.time();
+
+                            epochs_closed.add(1, &labels);
+
+                            if let Some(loaded) = loads_inbuf.remove(epoch) {
+                                for (worker, (state_key, change)) in loaded {
+                                    assert!(worker == worker_index);
+                                    if state_key == high_water_key {
+                                        if let StateChange::Upsert(state) = change {
+                                            high_water_epoch = Some(unwrap_any!(Python::with_gil(
+                                                |py| state.bind(py).extract::<u64>()
+                                            )));
+                                        }
+                                    }
+                                }
+                            }
+
+                            if let Some(sink) = part.as_ref() {
+                                let already_flushed = flushed_through
+                                    .map(|flushed| flushed >= *epoch)
+                                    .unwrap_or(false);
+                                if !already_flushed {
+                                    unwrap_any!(Python::with_gil(|py| sink
+                                        .flush(py)
+                                        .reraise("error flushing output partition")));
+                                    unwrap_any!(Python::with_gil(|py| sink
+                                        .on_epoch_complete(py, *epoch)
+                                        .reraise("error calling `StatelessSinkPartition.on_epoch_complete`")));
+                                    flushed_through = Some(*epoch);
+
+                                    if is_idempotent {
+                                        let state = Python::with_gil(|py| {
+                                            TdPyAny::from((*epoch).into_py(py))
+                                        });
+                                        let snap = Snapshot(
+                                            step_id.clone(),
+                                            high_water_key.clone(),
+                                            StateChange::Upsert(state),
+                                        );
+                                        snap_output.activate().session(snap_cap).give(snap);
+                                    }
+                                }
+                            }
+                        },
+                    );
                 });
             }
         });
 
-        Ok(downstream)
+        Ok((clock, snap))
     }
 }
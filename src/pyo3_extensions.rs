@@ -158,6 +158,7 @@ impl PartialEq for TdPyAny {
 ///
 /// To actually call, you must [`bind`] it and use the bound interface
 /// in order to not need to have a dual `TdPyX` vs `TdBoundX`.
+#[derive(Clone)]
 pub(crate) struct TdPyCallable(PyObject);
 
 /// Have PyO3 do type checking to ensure we only make from callable
@@ -3,6 +3,7 @@
 //! For a user-centric version of recovery, read the
 //! `bytewax.recovery` Python module docstring. Read that first.
 
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::collections::BTreeSet;
 use std::collections::HashMap;
@@ -15,8 +16,14 @@ use std::hash::Hash;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::JoinHandle;
 
 use chrono::TimeDelta;
+use opentelemetry::KeyValue;
 use pyo3::create_exception;
 use pyo3::exceptions::PyFileNotFoundError;
 use pyo3::exceptions::PyRuntimeError;
@@ -49,6 +56,7 @@ use tracing::instrument;
 use crate::errors::PythonException;
 use crate::inputs::EpochInterval;
 use crate::pyo3_extensions::TdPyAny;
+use crate::pyo3_extensions::TdPyCallable;
 use crate::timely::*;
 use crate::unwrap_any;
 
@@ -179,6 +187,39 @@ impl<'py> FromPyObject<'py> for BackupInterval {
     }
 }
 
+/// Maximum size in bytes of a single serialized state snapshot.
+///
+/// Snapshots larger than this are rejected with a clear error rather
+/// than being handed to the recovery backend, which would otherwise
+/// fail with an opaque error of its own once the row is too big for
+/// it to store.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct MaxSnapshotSize(usize);
+
+impl Default for MaxSnapshotSize {
+    /// SQLite's own default `SQLITE_MAX_LENGTH`, since that's the
+    /// backend recovery partitions are stored in.
+    fn default() -> Self {
+        Self(1_000_000_000)
+    }
+}
+
+impl IntoPy<Py<PyAny>> for MaxSnapshotSize {
+    fn into_py(self, py: Python<'_>) -> Py<PyAny> {
+        self.0.into_py(py)
+    }
+}
+
+impl<'py> FromPyObject<'py> for MaxSnapshotSize {
+    fn extract_bound(obj: &Bound<'py, PyAny>) -> PyResult<Self> {
+        if let Ok(size) = obj.extract::<usize>() {
+            Ok(Self(size))
+        } else {
+            Err(PyTypeError::new_err("max snapshot size must be an `int`"))
+        }
+    }
+}
+
 /// To resume a dataflow execution, you need to know which epoch to
 /// resume for state, but also which execution to label progress data
 /// with.
@@ -236,9 +277,7 @@ impl std::fmt::Display for StepId {
 /// be hashable, have equality, debug printable, and is serde-able and
 /// we can't guarantee those things are correct on any arbitrary
 /// Python type.
-#[derive(
-    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, FromPyObject,
-)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, FromPyObject)]
 pub(crate) struct StateKey(pub(crate) String);
 
 impl IntoPy<Py<PyAny>> for StateKey {
@@ -254,6 +293,57 @@ impl std::fmt::Display for StateKey {
     }
 }
 
+/// Whether [`StateKey`]'s [`Ord`] impl sorts keys that parse as
+/// integers by their numeric value, rather than plain lexicographic
+/// string order.
+///
+/// `StateKey`'s ordering only drives the iteration order of the
+/// `BTreeMap`/`BTreeSet` collections keyed by it (e.g.
+/// `StatefulBatchOp::stateful_batch`'s `logics`), which exists to make
+/// output byte-for-byte reproducible run to run; it has no bearing on
+/// routing, which hashes the key instead. Off by default, since it
+/// changes that iteration order for any dataflow already relying on
+/// today's lexicographic behavior. With integer-like keys stored as
+/// strings (e.g. `"9"`, `"10"`), lexicographic order sorts `"10"`
+/// before `"9"`, which can surprise when inspecting a recovery store
+/// or when snapshots are emitted in key order. Set
+/// `BYTEWAX_STATE_KEY_NUMERIC_ORDER=true` to sort keys that parse as
+/// an integer by that value instead. Checked once and cached, since
+/// this isn't something you'd toggle mid-run.
+fn numeric_state_key_order() -> bool {
+    static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var("BYTEWAX_STATE_KEY_NUMERIC_ORDER").as_deref() == Ok("true")
+    })
+}
+
+impl PartialOrd for StateKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders by the underlying string, unless
+/// [`numeric_state_key_order`] is on and both keys parse as integers,
+/// in which case orders by that numeric value instead. Ties (e.g.
+/// `"9"` and `"09"`, which parse to the same value but aren't the same
+/// string) always fall back to the string comparison, so two distinct
+/// keys can never compare equal here, which the `BTreeMap`/`BTreeSet`
+/// collections keyed by `StateKey` rely on.
+impl Ord for StateKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if numeric_state_key_order() {
+            if let (Ok(this), Ok(other)) = (self.0.parse::<i64>(), other.0.parse::<i64>()) {
+                let by_value = this.cmp(&other);
+                if by_value != std::cmp::Ordering::Equal {
+                    return by_value;
+                }
+            }
+        }
+        self.0.cmp(&other.0)
+    }
+}
+
 /// Each operator's state is modeled as as key-value store, with
 /// [`StateKey`] being the key, and this enum representing changes to
 /// the value.
@@ -289,6 +379,68 @@ struct SnapshotEpoch(u64);
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct SerializedSnapshot(StepId, StateKey, SnapshotEpoch, Option<Vec<u8>>);
 
+/// A read-only, Python-visible view of a [`SerializedSnapshot`].
+///
+/// Returned by [`read_snapshots`] so a custom recovery tool written in
+/// Python can index and query the contents of a recovery partition
+/// without needing to speak SQLite itself.
+#[pyclass(module = "bytewax.recovery")]
+struct RecoverySnapshot {
+    #[pyo3(get)]
+    step_id: StepId,
+    #[pyo3(get)]
+    key: StateKey,
+    #[pyo3(get)]
+    epoch: u64,
+    #[pyo3(get)]
+    ser_change: Option<Vec<u8>>,
+}
+
+impl From<SerializedSnapshot> for RecoverySnapshot {
+    fn from(
+        SerializedSnapshot(step_id, key, SnapshotEpoch(epoch), ser_change): SerializedSnapshot,
+    ) -> Self {
+        Self {
+            step_id,
+            key,
+            epoch,
+            ser_change,
+        }
+    }
+}
+
+/// A read-only, Python-visible view of a [`SerializedSnapshot`] handed
+/// to `RecoveryConfig.on_snapshot`.
+///
+/// Deliberately omits the serialized bytes that [`RecoverySnapshot`]
+/// carries: `on_snapshot` is for observing recovery traffic (e.g. to
+/// export a `snapshot_bytes` metric per step), not for inspecting or
+/// mutating state, so only `byte_len` is exposed.
+#[pyclass(module = "bytewax.recovery")]
+struct SnapshotInfo {
+    #[pyo3(get)]
+    step_id: StepId,
+    #[pyo3(get)]
+    key: StateKey,
+    #[pyo3(get)]
+    epoch: u64,
+    #[pyo3(get)]
+    byte_len: Option<usize>,
+}
+
+impl From<&SerializedSnapshot> for SnapshotInfo {
+    fn from(
+        SerializedSnapshot(step_id, key, SnapshotEpoch(epoch), ser_change): &SerializedSnapshot,
+    ) -> Self {
+        Self {
+            step_id: step_id.clone(),
+            key: key.clone(),
+            epoch: *epoch,
+            byte_len: ser_change.as_ref().map(Vec::len),
+        }
+    }
+}
+
 /// Configuration settings for recovery.
 ///
 /// :arg db_dir: Local filesystem directory to search for recovery
@@ -303,39 +455,151 @@ struct SerializedSnapshot(StepId, StateKey, SnapshotEpoch, Option<Vec<u8>>);
 ///     storage (e.g. S3). Defaults to zero duration.
 ///
 /// :type backup_interval: typing.Optional[datetime.timedelta]
+///
+/// :arg max_snapshot_size: Maximum size in bytes of a single state
+///     snapshot. If a snapshot would be larger than this, an error
+///     naming the offending step and key is raised rather than
+///     handing an oversized row to the recovery backend. Defaults to
+///     SQLite's own maximum row size.
+///
+/// :type max_snapshot_size: typing.Optional[int]
+///
+/// :arg additional_dirs: Extra local filesystem directories to mirror
+///     every snapshot write to, in addition to `db_dir`, e.g. a
+///     network-mounted volume backed by durable remote storage. Each
+///     directory must already contain the same set of initialized
+///     partitions as `db_dir` (see `bytewax.recovery`'s `init_db_dir`).
+///     A mirror write is not folded into the dataflow's backpressure
+///     clock and always runs on a dedicated background thread
+///     regardless of `background_snapshot_writes`. Each epoch's mirror
+///     writes are still flushed on the worker thread before it moves
+///     on, so a slow mirror (e.g. a flaky network mount) stalls the
+///     worker the same way a slow `db_dir` would; a mirror whose
+///     writes fail is held for retry without stopping the dataflow.
+///     On resume, `db_dir` is tried first; if it's missing or
+///     fails to open, each of `additional_dirs` is tried in turn and
+///     the first one that opens is resumed from instead, with a
+///     warning logged naming which directory was used. Defaults to no
+///     mirrors.
+///
+/// :type additional_dirs: typing.Optional[typing.List[pathlib.Path]]
+///
+/// :arg tolerate_snapshot_errors: If a state snapshot fails to
+///     serialize (e.g. it holds an unpicklable object, or it's larger
+///     than `max_snapshot_size`), count it in the `snapshot_failures`
+///     metric, log a warning naming the offending step and key, and
+///     skip writing a snapshot for that key this epoch, instead of
+///     crashing the worker. That key's state on resume falls back to
+///     its last successfully written snapshot, so this trades a
+///     durability gap for that key against uptime. Defaults to
+///     `False`, which crashes the worker on the first such error.
+///
+/// :type tolerate_snapshot_errors: bool
+///
+/// :arg background_snapshot_writes: If set, each `db_dir` partition
+///     hands its serialized snapshots off to a dedicated background
+///     thread rather than writing them inline, so one partition's
+///     write can overlap with another's instead of them serializing
+///     behind each other. An epoch is still only declared durable once
+///     every background write for it has completed, so this doesn't
+///     change what "durable" means, just how much of the write is
+///     hidden behind other partitions' writes. Writes to
+///     `additional_dirs` mirrors are always backgrounded and aren't
+///     affected by this setting. Defaults to `False`.
+///
+/// :type background_snapshot_writes: bool
+///
+/// :arg background_compaction: Old, superseded snapshots are already
+///     compacted away every time a partition commits (at most once
+///     per `backup_interval`), keeping disk usage bounded. If set,
+///     that compaction pass runs on a dedicated background thread
+///     rather than inline, so it doesn't delay the epoch's commit.
+///     Reclaimed bytes are still reported via the
+///     `recovery_reclaimed_bytes` metric either way. Defaults to
+///     `False`.
+///
+/// :type background_compaction: bool
+///
+/// :arg on_snapshot: Called with a {py:obj}`SnapshotInfo` for every
+///     state snapshot right before it's written to the recovery
+///     partition, on whichever worker produced it. Useful for
+///     observing recovery traffic, e.g. to export a custom
+///     `snapshot_bytes` metric per step. Called while holding the
+///     GIL, so keep it fast; it can't mutate the snapshot, only
+///     observe it. Defaults to `None`, calling nothing.
+///
+/// :type on_snapshot: typing.Optional[typing.Callable[[SnapshotInfo], None]]
+///
+/// :arg pickle_protocol: `pickle` protocol version to use when
+///     serializing state snapshots. Pin this to a specific version
+///     (e.g. `4` or `5`) for cross-Python-version recovery, or to
+///     trade off serialized size against compatibility. Defaults to
+///     `None`, which uses `pickle`'s own default protocol.
+///
+/// :type pickle_protocol: typing.Optional[int]
 #[pyclass(module = "bytewax.recovery")]
 pub(crate) struct RecoveryConfig {
     #[pyo3(get)]
     db_dir: PathBuf,
     #[pyo3(get)]
     backup_interval: BackupInterval,
+    #[pyo3(get)]
+    max_snapshot_size: MaxSnapshotSize,
+    #[pyo3(get)]
+    additional_dirs: Vec<PathBuf>,
+    #[pyo3(get)]
+    tolerate_snapshot_errors: bool,
+    #[pyo3(get)]
+    background_snapshot_writes: bool,
+    #[pyo3(get)]
+    background_compaction: bool,
+    on_snapshot: Option<TdPyCallable>,
+    #[pyo3(get)]
+    pickle_protocol: Option<i32>,
 }
 
 #[pymethods]
 impl RecoveryConfig {
     #[new]
-    fn new(db_dir: PathBuf, backup_interval: Option<BackupInterval>) -> Self {
+    #[pyo3(signature=(db_dir, backup_interval=None, max_snapshot_size=None, additional_dirs=None, tolerate_snapshot_errors=false, background_snapshot_writes=false, background_compaction=false, on_snapshot=None, pickle_protocol=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        db_dir: PathBuf,
+        backup_interval: Option<BackupInterval>,
+        max_snapshot_size: Option<MaxSnapshotSize>,
+        additional_dirs: Option<Vec<PathBuf>>,
+        tolerate_snapshot_errors: bool,
+        background_snapshot_writes: bool,
+        background_compaction: bool,
+        on_snapshot: Option<TdPyCallable>,
+        pickle_protocol: Option<i32>,
+    ) -> Self {
         Self {
             db_dir,
             backup_interval: backup_interval.unwrap_or_default(),
+            max_snapshot_size: max_snapshot_size.unwrap_or_default(),
+            additional_dirs: additional_dirs.unwrap_or_default(),
+            tolerate_snapshot_errors,
+            background_snapshot_writes,
+            background_compaction,
+            on_snapshot,
+            pickle_protocol,
         }
     }
 }
 
 impl RecoveryConfig {
-    /// Build the Rust-side bundle from the Python-side recovery
-    /// config.
-    #[instrument(name = "build_recovery", skip_all)]
-    pub(crate) fn build(&self, py: Python) -> PyResult<(RecoveryBundle, BackupInterval)> {
+    /// Scan a directory for initialized recovery partitions and build
+    /// the bundle of handles used to write to them.
+    fn scan_dir(py: Python, dir: &Path) -> PyResult<RecoveryBundle> {
         let mut part_paths = HashMap::new();
         let sqlite_ext = OsStr::new("sqlite3");
-        if !self.db_dir.is_dir() {
+        if !dir.is_dir() {
             return Err(PyFileNotFoundError::new_err(format!(
-                "recovery directory {:?} does not exist; see the `bytewax.recovery` module docstring for more info",
-                self.db_dir
+                "recovery directory {dir:?} does not exist; see the `bytewax.recovery` module docstring for more info",
             )));
         }
-        for entry in fs::read_dir(self.db_dir.clone()).reraise("Error listing recovery DB dir")? {
+        for entry in fs::read_dir(dir).reraise("Error listing recovery DB dir")? {
             let path = entry.reraise("Error accessing recovery DB file")?.path();
             if path.extension().map_or(false, |ext| *ext == *sqlite_ext) {
                 let part =
@@ -350,13 +614,78 @@ impl RecoveryConfig {
             }
         }
 
-        let bundle = RecoveryBundle {
+        Ok(RecoveryBundle {
             part_paths: Rc::new(part_paths),
             built_parts: Rc::new(RefCell::new(HashMap::new())),
-        };
-        let backup_interval = self.backup_interval;
+        })
+    }
 
-        Ok((bundle, backup_interval))
+    /// Build the Rust-side bundle from the Python-side recovery
+    /// config.
+    ///
+    /// Returns the primary bundle used for the resume calculation and
+    /// live reads, plus a bundle for each `additional_dirs` mirror
+    /// that live snapshot writes should also fan out to.
+    ///
+    /// If `db_dir` can't be opened, falls back to resuming from the
+    /// first `additional_dirs` entry that can, since mirrors are
+    /// written as part of the same recovery epoch as `db_dir` and so
+    /// carry the same data. Only errors out if none of them can be
+    /// opened either.
+    #[instrument(name = "build_recovery", skip_all)]
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn build(
+        &self,
+        py: Python,
+    ) -> PyResult<(
+        RecoveryBundle,
+        Vec<RecoveryBundle>,
+        BackupInterval,
+        MaxSnapshotSize,
+        bool,
+        bool,
+        bool,
+        Option<TdPyCallable>,
+        Option<i32>,
+    )> {
+        let bundle = Self::scan_dir(py, &self.db_dir).or_else(|err| {
+            self.additional_dirs
+                .iter()
+                .find_map(|dir| {
+                    let mirror_bundle = Self::scan_dir(py, dir).ok()?;
+                    tracing::warn!(
+                        "primary recovery directory {:?} unavailable ({err}); \
+                        resuming from mirror {dir:?} instead",
+                        self.db_dir,
+                    );
+                    Some(mirror_bundle)
+                })
+                .ok_or(err)
+        })?;
+        let mirror_bundles = self
+            .additional_dirs
+            .iter()
+            .map(|dir| Self::scan_dir(py, dir))
+            .collect::<PyResult<Vec<_>>>()?;
+        let backup_interval = self.backup_interval;
+        let max_snapshot_size = self.max_snapshot_size;
+        let tolerate_snapshot_errors = self.tolerate_snapshot_errors;
+        let background_snapshot_writes = self.background_snapshot_writes;
+        let background_compaction = self.background_compaction;
+        let on_snapshot = self.on_snapshot.clone();
+        let pickle_protocol = self.pickle_protocol;
+
+        Ok((
+            bundle,
+            mirror_bundles,
+            backup_interval,
+            max_snapshot_size,
+            tolerate_snapshot_errors,
+            background_snapshot_writes,
+            background_compaction,
+            on_snapshot,
+            pickle_protocol,
+        ))
     }
 }
 
@@ -438,6 +767,21 @@ struct RecoveryPart {
     /// This is [`Rc<RefCell>`] so that our reader and writer structs
     /// can maintain an internal connection reference across batches.
     conn: Rc<RefCell<Connection>>,
+    /// File this partition was opened from, if any. Kept around so a
+    /// [`BackgroundWriter`] can open its own connection to the same
+    /// file on its own thread rather than sharing `conn`, which isn't
+    /// [`Send`]. `None` for the in-memory connections used in tests.
+    file: Option<PathBuf>,
+    /// Set while this partition's store is failing to write, e.g.
+    /// because the underlying disk has gone read-only.
+    ///
+    /// Shared between the writers built by [`RecoveryPart::ex_writer`]
+    /// / [`RecoveryPart::front_writer`] / [`RecoveryPart::snap_writer`]
+    /// (via [`DegradableWriter`]) and the [`RecoveryCommitter`] built
+    /// by [`RecoveryPart::committer`], so that a degraded partition's
+    /// resume point stops advancing instead of committing past data
+    /// that never made it to the store.
+    degraded: Rc<Cell<bool>>,
 }
 
 // The `'static` lifetime within [`Migrations`] is saying that the
@@ -532,9 +876,9 @@ struct PartitionMetaWriter {
 impl Writer for PartitionMetaWriter {
     type Item = PartitionMeta;
 
-    fn write_batch(&mut self, items: Vec<Self::Item>) {
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
         let mut conn = self.conn.borrow_mut();
-        let txn = conn.transaction().unwrap();
+        let txn = conn.transaction()?;
         for part in items {
             tracing::trace!("Writing {part:?}");
             let PartitionMeta(part_index, part_count) = part;
@@ -542,10 +886,10 @@ impl Writer for PartitionMetaWriter {
                 "INSERT INTO parts (part_index, part_count)
                  VALUES (?1, ?2)",
                 (part_index.0, part_count.0),
-            )
-            .unwrap();
+            )?;
         }
-        txn.commit().unwrap();
+        txn.commit()?;
+        Ok(())
     }
 }
 
@@ -556,9 +900,9 @@ struct ExecutionMetaWriter {
 impl Writer for ExecutionMetaWriter {
     type Item = ExecutionMeta;
 
-    fn write_batch(&mut self, items: Vec<Self::Item>) {
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
         let mut conn = self.conn.borrow_mut();
-        let txn = conn.transaction().unwrap();
+        let txn = conn.transaction()?;
         for ex in items {
             tracing::trace!("Writing {ex:?}");
             let ExecutionMeta(ex_num, worker_count, resume_epoch) = ex;
@@ -568,10 +912,10 @@ impl Writer for ExecutionMetaWriter {
                 "INSERT INTO exs (ex_num, worker_count, resume_epoch)
                  VALUES (?1, ?2, ?3)",
                 (ex_num.0, worker_count.0, resume_epoch.0),
-            )
-            .unwrap();
+            )?;
         }
-        txn.commit().unwrap();
+        txn.commit()?;
+        Ok(())
     }
 }
 
@@ -582,9 +926,9 @@ struct FrontierWriter {
 impl Writer for FrontierWriter {
     type Item = FrontierMeta;
 
-    fn write_batch(&mut self, items: Vec<Self::Item>) {
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
         let mut conn = self.conn.borrow_mut();
-        let txn = conn.transaction().unwrap();
+        let txn = conn.transaction()?;
         for front in items {
             tracing::trace!("Writing {front:?}");
             let FrontierMeta(ex, worker_count, wf) = front;
@@ -594,10 +938,10 @@ impl Writer for FrontierWriter {
                  ON CONFLICT (ex_num, worker_index) DO UPDATE
                  SET worker_frontier = EXCLUDED.worker_frontier",
                 (ex.0, worker_count.0, wf.0),
-            )
-            .unwrap();
+            )?;
         }
-        txn.commit().unwrap();
+        txn.commit()?;
+        Ok(())
     }
 }
 
@@ -608,9 +952,9 @@ struct SerializedSnapshotWriter {
 impl Writer for SerializedSnapshotWriter {
     type Item = SerializedSnapshot;
 
-    fn write_batch(&mut self, items: Vec<Self::Item>) {
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
         let mut conn = self.conn.borrow_mut();
-        let txn = conn.transaction().unwrap();
+        let txn = conn.transaction()?;
         for snap in items {
             tracing::trace!("Writing {snap:?}");
             let SerializedSnapshot(step_id, state_key, snap_epoch, ser_change) = snap;
@@ -620,13 +964,168 @@ impl Writer for SerializedSnapshotWriter {
                  ON CONFLICT (step_id, state_key, snap_epoch) DO UPDATE
                  SET ser_change = EXCLUDED.ser_change",
                 (step_id.0, state_key.0, snap_epoch.0, ser_change),
-            )
-            .unwrap();
+            )?;
+        }
+        txn.commit()?;
+        Ok(())
+    }
+}
+
+/// Wraps a [`Writer`] to track whether the partition it writes to is
+/// currently failing, e.g. because its backing store has gone
+/// read-only.
+///
+/// The flag is an [`Rc<Cell>`] shared with this same partition's
+/// [`RecoveryCommitter`] (see [`RecoveryPart::degraded`]), so that a
+/// partition's resume point stops advancing while its writer can't
+/// write, rather than committing past snapshots that never made it to
+/// the store. Write errors otherwise pass through unchanged;
+/// `partd_write` in `timely.rs` is what decides to retry rather than
+/// panic.
+struct DegradableWriter<W> {
+    inner: W,
+    degraded: Rc<Cell<bool>>,
+}
+
+impl<W: Writer> Writer for DegradableWriter<W> {
+    type Item = W::Item;
+
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self.inner.write_batch(items);
+        self.degraded.set(result.is_err());
+        result
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self.inner.flush();
+        self.degraded.set(result.is_err());
+        result
+    }
+}
+
+enum BackgroundWriterMsg<T> {
+    Batch(Vec<T>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// Runs a [`Writer`] on a dedicated background thread, so this
+/// partition's writes can overlap with another partition's instead of
+/// serializing behind it.
+///
+/// Batches are handed off through a bounded channel:
+/// [`Writer::write_batch`] only blocks once `capacity` batches are
+/// already queued and waiting on the background thread, not for the
+/// actual write. [`Writer::flush`] blocks until every batch handed off
+/// so far has actually been written; `partd_write` calls this before
+/// declaring an epoch's writes complete, so durability is unaffected,
+/// only the overlap between partitions changes.
+struct BackgroundWriter<T> {
+    tx: mpsc::SyncSender<BackgroundWriterMsg<T>>,
+    handle: Option<JoinHandle<()>>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl<T: Send + 'static> BackgroundWriter<T> {
+    /// `build` is run on the background thread itself, rather than
+    /// here, since the inner writer usually isn't [`Send`] (e.g. it
+    /// holds a [`Connection`] behind an [`Rc<RefCell>`]).
+    fn spawn<W>(capacity: usize, build: impl FnOnce() -> W + Send + 'static) -> Self
+    where
+        W: Writer<Item = T>,
+    {
+        let (tx, rx) = mpsc::sync_channel(capacity);
+        let error = Arc::new(Mutex::new(None));
+        let thread_error = error.clone();
+        let handle = thread::spawn(move || {
+            let mut inner = build();
+            for msg in rx {
+                match msg {
+                    BackgroundWriterMsg::Batch(items) => {
+                        if let Err(err) = inner.write_batch(items) {
+                            *thread_error.lock().unwrap() = Some(err.to_string());
+                        }
+                    }
+                    BackgroundWriterMsg::Flush(ack) => {
+                        // Best-effort; if the receiver hung up it's
+                        // already stopped waiting.
+                        let _ = ack.send(());
+                    }
+                }
+            }
+        });
+
+        Self {
+            tx,
+            handle: Some(handle),
+            error,
+        }
+    }
+
+    fn check_error(&self) -> Result<(), Box<dyn std::error::Error>> {
+        match self.error.lock().unwrap().take() {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: Send + 'static> Writer for BackgroundWriter<T> {
+    type Item = T;
+
+    fn write_batch(&mut self, items: Vec<T>) -> Result<(), Box<dyn std::error::Error>> {
+        self.check_error()?;
+        self.tx
+            .send(BackgroundWriterMsg::Batch(items))
+            .map_err(|_| "recovery background writer thread died")?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let (ack_tx, ack_rx) = mpsc::channel();
+        self.tx
+            .send(BackgroundWriterMsg::Flush(ack_tx))
+            .map_err(|_| "recovery background writer thread died")?;
+        ack_rx
+            .recv()
+            .map_err(|_| "recovery background writer thread died before acking flush")?;
+        self.check_error()
+    }
+}
+
+impl<T> Drop for BackgroundWriter<T> {
+    fn drop(&mut self) {
+        // Dropping `tx` closes the channel, which ends the thread's
+        // `for msg in rx` loop so it can exit on its own.
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
         }
-        txn.commit().unwrap();
     }
 }
 
+struct VecWriter(Arc<Mutex<Vec<i32>>>);
+
+impl Writer for VecWriter {
+    type Item = i32;
+
+    fn write_batch(&mut self, items: Vec<i32>) -> Result<(), Box<dyn std::error::Error>> {
+        self.0.lock().unwrap().extend(items);
+        Ok(())
+    }
+}
+
+#[test]
+fn background_writer_flush_waits_for_writes() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let inner_seen = seen.clone();
+    let mut writer = BackgroundWriter::spawn(2, move || VecWriter(inner_seen));
+
+    writer.write_batch(vec![1, 2]).unwrap();
+    writer.write_batch(vec![3]).unwrap();
+    writer.flush().unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2, 3]);
+}
+
 struct CommitWriter {
     conn: Rc<RefCell<Connection>>,
 }
@@ -634,9 +1133,9 @@ struct CommitWriter {
 impl Writer for CommitWriter {
     type Item = CommitMeta;
 
-    fn write_batch(&mut self, items: Vec<Self::Item>) {
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
         let mut conn = self.conn.borrow_mut();
-        let txn = conn.transaction().unwrap();
+        let txn = conn.transaction()?;
         for commit in items {
             tracing::trace!("Writing {commit:?}");
             let CommitMeta(part_idx, commit_epoch) = commit;
@@ -644,10 +1143,10 @@ impl Writer for CommitWriter {
                 "INSERT INTO commits (part_index, commit_epoch)
                  VALUES (?1, ?2)",
                 (part_idx.0, commit_epoch),
-            )
-            .unwrap();
+            )?;
         }
-        txn.commit().unwrap();
+        txn.commit()?;
+        Ok(())
     }
 }
 
@@ -935,33 +1434,74 @@ impl BatchIterator for CommitLoader {
     }
 }
 
-struct RecoveryCommitter {
-    conn: Rc<RefCell<Connection>>,
-    part_key: PartitionIndex,
+/// Deletes snapshots superseded by a commit on a dedicated thread with
+/// its own connection, so that GC pass doesn't delay the epoch's
+/// commit on the main dataflow thread.
+///
+/// Only the GC delete is backgrounded; the durable `commits` row is
+/// always written inline by [`RecoveryCommitter::commit`], since it's
+/// cheap and resuming correctly depends on it. A pending delete racing
+/// a read is harmless: the superseded rows it's removing are already
+/// ignored by [`SerializedSnapshotLoader`], which only ever reads the
+/// latest snapshot per `(step_id, state_key)`.
+struct BackgroundCompactor {
+    tx: mpsc::Sender<u64>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl BackgroundCompactor {
+    fn spawn(file: PathBuf, reclaimed_bytes: opentelemetry::metrics::Counter<u64>) -> Self {
+        let (tx, rx) = mpsc::channel::<u64>();
+        let handle = thread::spawn(move || {
+            let conn = Connection::open_with_flags(
+                &file,
+                OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .unwrap_or_else(|err| {
+                panic!("can't open recovery DB {file:?} on background compactor thread: {err}")
+            });
+            for epoch in rx {
+                let bytes = gc_snaps(&conn, epoch);
+                reclaimed_bytes.add(bytes, &[]);
+            }
+        });
+
+        Self {
+            tx,
+            handle: Some(handle),
+        }
+    }
+
+    fn compact(&self, epoch: u64) {
+        // Best-effort; if the thread died, `RecoveryCommitter::commit`
+        // already wrote the durable commit row, so at worst we leak
+        // disk space until the worker restarts.
+        let _ = self.tx.send(epoch);
+    }
 }
 
-impl Committer<u64> for RecoveryCommitter {
-    /// This will be called when `epoch` is the earliest possible
-    /// resume epoch.
-    fn commit(&mut self, epoch: &u64) {
-        tracing::trace!("Committing / GCing epoch {epoch:?}");
-        let mut conn = self.conn.borrow_mut();
-        let txn = conn.transaction().unwrap();
-        txn.execute(
-            "INSERT INTO commits (part_index, commit_epoch)
-             VALUES (?1, ?2)
-             ON CONFLICT (part_index) DO UPDATE
-             SET commit_epoch = EXCLUDED.commit_epoch",
-            (self.part_key.0, epoch),
-        )
-        .unwrap();
-        // Find the most recent snapshot including the commited epoch
-        // (since we can GC everything before that epoch). Then find
-        // all less recent snapshots and delete those. So we never
-        // want to delete a snapshot in the commited epoch, but since
-        // the most recent snapshot is not deleted it's ok for this to
-        // be `<=`.
-        txn.execute(
+impl Drop for BackgroundCompactor {
+    fn drop(&mut self) {
+        // Dropping `tx` closes the channel, ending the thread's `for
+        // epoch in rx` loop so it can exit on its own.
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Delete snapshots superseded by a commit of `epoch`, returning the
+/// number of bytes of `ser_change` data reclaimed.
+///
+/// Find the most recent snapshot including the committed epoch (since
+/// we can GC everything before that epoch). Then find all less recent
+/// snapshots and delete those. So we never want to delete a snapshot
+/// in the committed epoch, but since the most recent snapshot is not
+/// deleted it's ok for this to be `<=`.
+fn gc_snaps(conn: &Connection, epoch: u64) -> u64 {
+    let txn = conn.unchecked_transaction().unwrap();
+    let reclaimed_bytes: Option<i64> = txn
+        .query_row(
             "WITH max_epoch_snapshots AS (
              SELECT step_id, state_key, MAX(snap_epoch) AS snap_epoch
              FROM snaps
@@ -969,17 +1509,92 @@ impl Committer<u64> for RecoveryCommitter {
              GROUP BY step_id, state_key
              ),
              garbage_snapshots AS (
-             SELECT step_id, state_key, snaps.snap_epoch
+             SELECT step_id, state_key, snaps.snap_epoch, snaps.ser_change
              FROM snaps
              JOIN max_epoch_snapshots USING (step_id, state_key)
              WHERE snaps.snap_epoch < max_epoch_snapshots.snap_epoch
              )
-             DELETE FROM snaps
-             WHERE (step_id, state_key, snap_epoch) IN garbage_snapshots",
+             SELECT SUM(LENGTH(ser_change)) FROM garbage_snapshots",
             (epoch,),
+            |row| row.get(0),
         )
         .unwrap();
-        txn.commit().unwrap();
+    txn.execute(
+        "WITH max_epoch_snapshots AS (
+         SELECT step_id, state_key, MAX(snap_epoch) AS snap_epoch
+         FROM snaps
+         WHERE snap_epoch <= ?1
+         GROUP BY step_id, state_key
+         ),
+         garbage_snapshots AS (
+         SELECT step_id, state_key, snaps.snap_epoch
+         FROM snaps
+         JOIN max_epoch_snapshots USING (step_id, state_key)
+         WHERE snaps.snap_epoch < max_epoch_snapshots.snap_epoch
+         )
+         DELETE FROM snaps
+         WHERE (step_id, state_key, snap_epoch) IN garbage_snapshots",
+        (epoch,),
+    )
+    .unwrap();
+    txn.commit().unwrap();
+    reclaimed_bytes.unwrap_or(0) as u64
+}
+
+struct RecoveryCommitter {
+    conn: Rc<RefCell<Connection>>,
+    part_key: PartitionIndex,
+    /// If set, the GC pass runs on this dedicated thread instead of
+    /// inline; see [`BackgroundCompactor`].
+    compactor: Option<BackgroundCompactor>,
+    /// Only used when `compactor` is `None`; the background compactor
+    /// tracks its own copy of this same counter.
+    reclaimed_bytes: opentelemetry::metrics::Counter<u64>,
+    /// Shared with this partition's writers; see
+    /// [`RecoveryPart::degraded`].
+    degraded: Rc<Cell<bool>>,
+}
+
+impl Committer<u64> for RecoveryCommitter {
+    /// This will be called when `epoch` is the earliest possible
+    /// resume epoch.
+    ///
+    /// Skipped while this partition's store is known to be failing to
+    /// write, so the persisted resume point doesn't advance past
+    /// snapshots that never made it to disk; we'll catch up on a
+    /// later epoch once the writer reports the store is healthy
+    /// again.
+    fn commit(&mut self, epoch: &u64) {
+        if self.degraded.get() {
+            tracing::warn!(
+                "partition {:?} is degraded, skipping commit of epoch {epoch:?}",
+                self.part_key
+            );
+            return;
+        }
+
+        tracing::trace!("Committing / GCing epoch {epoch:?}");
+        {
+            let mut conn = self.conn.borrow_mut();
+            let txn = conn.transaction().unwrap();
+            txn.execute(
+                "INSERT INTO commits (part_index, commit_epoch)
+                 VALUES (?1, ?2)
+                 ON CONFLICT (part_index) DO UPDATE
+                 SET commit_epoch = EXCLUDED.commit_epoch",
+                (self.part_key.0, epoch),
+            )
+            .unwrap();
+            txn.commit().unwrap();
+        }
+
+        match &self.compactor {
+            Some(compactor) => compactor.compact(*epoch),
+            None => {
+                let reclaimed_bytes = gc_snaps(&self.conn.borrow(), *epoch);
+                self.reclaimed_bytes.add(reclaimed_bytes, &[]);
+            }
+        }
     }
 }
 
@@ -987,7 +1602,7 @@ impl Committer<u64> for RecoveryCommitter {
 fn gc_leaves_only_final_snap() {
     pyo3::prepare_freethreaded_python();
     let conn = Python::with_gil(|py| RecoveryPart::init_open_mem(py));
-    conn.snap_writer().write_batch(vec![
+    conn.snap_writer(false).write_batch(vec![
         SerializedSnapshot(
             StepId(String::from("step_1")),
             StateKey(String::from("a")),
@@ -1007,7 +1622,7 @@ fn gc_leaves_only_final_snap() {
             Some("PICKLED_DATA5".as_bytes().to_vec()),
         ),
     ]);
-    conn.committer(PartitionIndex(0)).commit(&5);
+    conn.committer(PartitionIndex(0), false).commit(&5);
 
     let found = conn
         .conn
@@ -1079,7 +1694,11 @@ impl RecoveryPart {
         ));
         setup_conn(py, &conn);
 
-        let _self = Self { conn };
+        let _self = Self {
+            conn,
+            file: Some(file.to_path_buf()),
+            degraded: Rc::new(Cell::new(false)),
+        };
         _self
             .part_writer()
             .write_batch(vec![PartitionMeta(index, count)]);
@@ -1098,14 +1717,22 @@ impl RecoveryPart {
         ));
         setup_conn(py, &conn);
 
-        Ok(Self { conn })
+        Ok(Self {
+            conn,
+            file: Some(file.to_path_buf()),
+            degraded: Rc::new(Cell::new(false)),
+        })
     }
 
     fn init_open_mem(py: Python) -> Self {
         let conn = Rc::new(RefCell::new(Connection::open_in_memory().unwrap()));
         setup_conn(py, &conn);
 
-        Self { conn }
+        Self {
+            conn,
+            file: None,
+            degraded: Rc::new(Cell::new(false)),
+        }
     }
 
     fn part_writer(&self) -> PartitionMetaWriter {
@@ -1114,21 +1741,55 @@ impl RecoveryPart {
         }
     }
 
-    fn ex_writer(&self) -> ExecutionMetaWriter {
-        ExecutionMetaWriter {
-            conn: self.conn.clone(),
+    fn ex_writer(&self) -> DegradableWriter<ExecutionMetaWriter> {
+        DegradableWriter {
+            inner: ExecutionMetaWriter {
+                conn: self.conn.clone(),
+            },
+            degraded: self.degraded.clone(),
         }
     }
 
-    fn front_writer(&self) -> FrontierWriter {
-        FrontierWriter {
-            conn: self.conn.clone(),
+    fn front_writer(&self) -> DegradableWriter<FrontierWriter> {
+        DegradableWriter {
+            inner: FrontierWriter {
+                conn: self.conn.clone(),
+            },
+            degraded: self.degraded.clone(),
         }
     }
 
-    fn snap_writer(&self) -> SerializedSnapshotWriter {
-        SerializedSnapshotWriter {
-            conn: self.conn.clone(),
+    /// Build a writer for serialized snapshots.
+    ///
+    /// If `background` is set, batches are handed off to a dedicated
+    /// thread with its own connection to this same file, rather than
+    /// written inline; see [`BackgroundWriter`].
+    fn snap_writer(&self, background: bool) -> Box<dyn Writer<Item = SerializedSnapshot>> {
+        let degraded = self.degraded.clone();
+        if background {
+            let file = self
+                .file
+                .clone()
+                .expect("background_snapshot_writes requires a file-backed recovery partition");
+            let inner = BackgroundWriter::spawn(4, move || {
+                let conn = Rc::new(RefCell::new(
+                    Connection::open_with_flags(
+                        &file,
+                        OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                    )
+                    .unwrap_or_else(|err| {
+                        panic!("can't open recovery DB {file:?} on background writer thread: {err}")
+                    }),
+                ));
+                Python::with_gil(|py| setup_conn(py, &conn));
+                SerializedSnapshotWriter { conn }
+            });
+            Box::new(DegradableWriter { inner, degraded })
+        } else {
+            let inner = SerializedSnapshotWriter {
+                conn: self.conn.clone(),
+            };
+            Box::new(DegradableWriter { inner, degraded })
         }
     }
 
@@ -1161,10 +1822,34 @@ impl RecoveryPart {
         CommitLoader::new(self.conn.clone())
     }
 
-    fn committer(&self, part_key: PartitionIndex) -> RecoveryCommitter {
+    /// Build a committer for this partition.
+    ///
+    /// If `background_compaction` is set, the GC pass that deletes
+    /// snapshots superseded by the commit is handed off to a
+    /// dedicated thread with its own connection to this same file,
+    /// rather than run inline; see [`BackgroundCompactor`].
+    fn committer(&self, part_key: PartitionIndex, background_compaction: bool) -> RecoveryCommitter {
+        let reclaimed_bytes = opentelemetry::global::meter("bytewax")
+            .u64_counter("recovery_reclaimed_bytes")
+            .with_description(
+                "total bytes of superseded snapshots deleted by compaction",
+            )
+            .init();
+        let compactor = if background_compaction {
+            let file = self
+                .file
+                .clone()
+                .expect("background_compaction requires a file-backed recovery partition");
+            Some(BackgroundCompactor::spawn(file, reclaimed_bytes.clone()))
+        } else {
+            None
+        };
         RecoveryCommitter {
             conn: self.conn.clone(),
             part_key,
+            compactor,
+            reclaimed_bytes,
+            degraded: self.degraded.clone(),
         }
     }
 
@@ -1383,6 +2068,89 @@ fn init_db_dir(py: Python, db_dir: PathBuf, count: PartitionCount) -> PyResult<(
     Ok(())
 }
 
+/// Compute the worker index a state key routes to.
+///
+/// Mirrors the hashing `stateful` operators use internally to route
+/// `(key, value)`s to the worker that owns that key's state, so custom
+/// Python-side operators can pre-shuffle data to match without
+/// duplicating the hash function by hand.
+///
+/// This is also the whole trick behind rescaling: on resume, loaded
+/// snapshots are routed through this exact same hash using the
+/// _new_ execution's worker count, so state automatically ends up on
+/// the worker that will own that key going forward. There is no
+/// separate "rekey the recovery store" step to run before resuming
+/// with a different worker count; it happens for you as part of
+/// loading. Call this yourself, with the worker count you're about
+/// to resume with, if you want to know ahead of time where a given
+/// key's state will land, e.g. to sanity-check a planned resize. See
+/// the rescaling guide in the docs for the full picture.
+///
+/// State keys are already just plain strings on the Python side, so
+/// there's no separate step to "construct" one; pass the string key
+/// directly.
+///
+/// :arg key: State key to hash.
+///
+/// :type key: str
+///
+/// :arg worker_count: Number of workers in this execution.
+///
+/// :type worker_count: int
+#[pyfunction]
+fn assign_worker_index(key: StateKey, worker_count: usize) -> usize {
+    let pf = BuildHasherDefault::<SeaHasher>::default();
+    pf.assign(&key) % worker_count
+}
+
+/// Read the current snapshots out of a recovery partition.
+///
+/// This is a debugging and tooling aid for custom recovery backends:
+/// it lets a Python script inspect exactly what a partition holds
+/// without needing to speak SQLite itself. Like loading on resume,
+/// this only returns the most recently written snapshot for each
+/// `(step_id, key)`; it does not return the full write history.
+///
+/// :arg part_file: Path to a single recovery partition's SQLite
+///     database file, e.g. one created by
+///     {py:obj}`~bytewax.recovery.init_db_dir`.
+///
+/// :type part_file: pathlib.Path
+///
+/// :returns: All current snapshots in the partition.
+///
+/// :rtype: typing.List[RecoverySnapshot]
+#[pyfunction]
+fn read_snapshots(py: Python, part_file: PathBuf) -> PyResult<Vec<RecoverySnapshot>> {
+    let part = RecoveryPart::open(py, &part_file).reraise("error opening recovery partition")?;
+    let mut loader = part.snap_loader(ResumeEpoch(u64::MAX));
+
+    let mut snaps = Vec::new();
+    while let Some(batch) = loader.next_batch() {
+        snaps.extend(batch.into_iter().map(RecoverySnapshot::from));
+    }
+    Ok(snaps)
+}
+
+/// Rescaling relies on [`assign_worker_index`] being stable for a
+/// given key and worker count, and always landing in range, no
+/// matter how the worker count changes between one execution and
+/// the next; a rescale is nothing more than resuming with a
+/// different `worker_count`, so the "rekeying" of the recovery
+/// store's snapshots is really just this function being called again
+/// downstream with the new count.
+#[test]
+fn assign_worker_index_is_stable_and_in_range() {
+    let key = StateKey(String::from("some-key"));
+
+    for worker_count in 1..=16 {
+        let first = assign_worker_index(key.clone(), worker_count);
+        let second = assign_worker_index(key.clone(), worker_count);
+        assert_eq!(first, second, "same worker_count must hash the same way");
+        assert!(first < worker_count, "must route within the cluster");
+    }
+}
+
 trait FrontierOp<S, D>
 where
     S: Scope,
@@ -1514,14 +2282,71 @@ where
     /// Although the [`StepId`] and [`StateKey`] are both already
     /// within the [`SerializedSnapshot`], duplicate them in the key
     /// position so we can partition and route on them.
-    fn ser_snap(&self) -> Stream<S, ((StepId, StateKey), SerializedSnapshot)>;
+    ///
+    /// This does all its work, including `pickle.dumps`, while
+    /// holding the GIL, so it can't be sped up by dispatching keys to
+    /// a Rayon pool: CPython holds the GIL for the entirety of a
+    /// `pickle.dumps` call, so worker threads in such a pool would
+    /// just queue up behind each other waiting for it, not run
+    /// concurrently. The `ser_snap_duration_seconds` histogram below
+    /// is here so a real bottleneck in this step shows up in metrics
+    /// before reaching for a parallelization strategy that would
+    /// require moving off pickle (or off the GIL) to actually help.
+    ///
+    /// If a snapshot serializes to more than `max_snapshot_size`
+    /// bytes, or `pickle.dumps` itself raises (e.g. an unpicklable
+    /// object), this panics with a Python exception naming the
+    /// offending step and key rather than handing a bad row to the
+    /// recovery backend, which would otherwise fail with an opaque
+    /// error of its own.
+    ///
+    /// If `tolerate_snapshot_errors` is set, instead of panicking,
+    /// this counts the failure in the `snapshot_failures` counter,
+    /// logs a warning naming the step and key, and skips writing a
+    /// snapshot for that key this epoch, leaving its last
+    /// successfully written snapshot in place on resume. This trades
+    /// a durability gap for that key for keeping the worker up, so it
+    /// defaults to off.
+    ///
+    /// If `on_snapshot` is given, it's called with a [`SnapshotInfo`]
+    /// for every snapshot produced here, right before it's handed
+    /// downstream to be written.
+    ///
+    /// `pickle_protocol` is passed straight through to
+    /// `pickle.dumps`; `None` uses `pickle`'s own default.
+    fn ser_snap(
+        &self,
+        max_snapshot_size: MaxSnapshotSize,
+        tolerate_snapshot_errors: bool,
+        on_snapshot: Option<TdPyCallable>,
+        pickle_protocol: Option<i32>,
+    ) -> Stream<S, ((StepId, StateKey), SerializedSnapshot)>;
 }
 
 impl<S> SerializeSnapshotOp<S> for Stream<S, Snapshot>
 where
     S: Scope<Timestamp = u64>,
 {
-    fn ser_snap(&self) -> Stream<S, ((StepId, StateKey), SerializedSnapshot)> {
+    fn ser_snap(
+        &self,
+        max_snapshot_size: MaxSnapshotSize,
+        tolerate_snapshot_errors: bool,
+        on_snapshot: Option<TdPyCallable>,
+        pickle_protocol: Option<i32>,
+    ) -> Stream<S, ((StepId, StateKey), SerializedSnapshot)> {
+        let meter = opentelemetry::global::meter("bytewax");
+        let ser_snap_histogram = meter
+            .f64_histogram("ser_snap_duration_seconds")
+            .with_description("`ser_snap` duration in seconds")
+            .init();
+        let snapshot_failures = meter
+            .u64_counter("snapshot_failures")
+            .with_description(
+                "number of snapshots skipped because they failed to serialize, \
+                 only counted when `RecoveryConfig.tolerate_snapshot_errors` is set",
+            )
+            .init();
+
         // Effectively map-with-epoch.
         self.unary(Pipeline, "ser_snap", move |_init_cap, _info| {
             let mut inbuf = Vec::new();
@@ -1532,44 +2357,82 @@ where
                     incoming.swap(&mut inbuf);
 
                     let epoch = cap.time();
+                    let now = std::time::Instant::now();
                     Python::with_gil(|py| {
-                        let ser_snaps =
-                            inbuf
-                                .drain(..)
-                                .map(|Snapshot(step_id, state_key, snap_change)| {
-                                    let ser_change = match snap_change {
-                                        StateChange::Upsert(snap) => {
-                                            let snap = PyObject::from(snap);
-                                            let bytes = unwrap_any!(|| -> PyResult<Vec<u8>> {
-                                                Ok(pickle
-                                                    .bind(py)
-                                                    .call_method1(
-                                                        intern!(py, "dumps"),
-                                                        (snap.bind(py),),
-                                                    )?
-                                                    .downcast::<PyBytes>()?
-                                                    .as_bytes()
-                                                    .to_vec())
-                                            }(
-                                            ));
-                                            Some(bytes)
+                        let ser_snaps = inbuf.drain(..).filter_map(
+                            |Snapshot(step_id, state_key, snap_change)| {
+                                let ser_change = match snap_change {
+                                    StateChange::Upsert(snap) => {
+                                        let snap = PyObject::from(snap);
+                                        let res = (|| -> PyResult<Vec<u8>> {
+                                            let bytes = pickle
+                                                .bind(py)
+                                                .call_method1(
+                                                    intern!(py, "dumps"),
+                                                    (snap.bind(py), pickle_protocol),
+                                                )?
+                                                .downcast::<PyBytes>()?
+                                                .as_bytes()
+                                                .to_vec();
+                                            if bytes.len() > max_snapshot_size.0 {
+                                                return Err(PyValueError::new_err(format!(
+                                                    "state snapshot for step {step_id} key {state_key} is {} bytes, \
+                                                     which is over the maximum snapshot size of {} bytes; \
+                                                     either shrink the state you're keeping for this key or \
+                                                     raise `RecoveryConfig.max_snapshot_size`",
+                                                    bytes.len(),
+                                                    max_snapshot_size.0,
+                                                )));
+                                            }
+                                            Ok(bytes)
+                                        })();
+
+                                        match res {
+                                            Ok(bytes) => Some(bytes),
+                                            Err(err) if tolerate_snapshot_errors => {
+                                                tracing::warn!(
+                                                    "Failed to snapshot step {step_id} key \
+                                                     {state_key}, skipping this snapshot: {err}"
+                                                );
+                                                snapshot_failures.add(
+                                                    1,
+                                                    &[KeyValue::new(
+                                                        "step_id",
+                                                        step_id.0.clone(),
+                                                    )],
+                                                );
+                                                return None;
+                                            }
+                                            Err(err) => Some(unwrap_any!(Err::<Vec<u8>, _>(err))),
                                         }
-                                        StateChange::Discard => None,
-                                    };
-
-                                    let snap_epoch = SnapshotEpoch(*epoch);
-                                    let ser_snap = SerializedSnapshot(
-                                        step_id.clone(),
-                                        state_key.clone(),
-                                        snap_epoch,
-                                        ser_change,
-                                    );
-                                    let key = (step_id, state_key);
-
-                                    (key, ser_snap)
-                                });
+                                    }
+                                    StateChange::Discard => None,
+                                };
+
+                                let snap_epoch = SnapshotEpoch(*epoch);
+                                let ser_snap = SerializedSnapshot(
+                                    step_id.clone(),
+                                    state_key.clone(),
+                                    snap_epoch,
+                                    ser_change,
+                                );
+
+                                if let Some(on_snapshot) = &on_snapshot {
+                                    let info = SnapshotInfo::from(&ser_snap);
+                                    unwrap_any!(on_snapshot
+                                        .bind(py)
+                                        .call1((info,))
+                                        .reraise("error calling `RecoveryConfig.on_snapshot`"));
+                                }
+
+                                let key = (step_id, state_key);
+
+                                Some((key, ser_snap))
+                            },
+                        );
                         ser_snaps_output.session(&cap).give_iterator(ser_snaps);
                     });
+                    ser_snap_histogram.record(now.elapsed().as_secs_f64(), &[]);
                 });
             }
         })
@@ -1689,12 +2552,19 @@ where
     /// You'll add this on at the end of the production dataflow.
     ///
     /// Probe the downstream clock to rate limit the dataflow.
+    #[allow(clippy::too_many_arguments)]
     fn write_recovery(
         &self,
         resume_from: ResumeFrom,
         bundle: RecoveryBundle,
         epoch_interval: EpochInterval,
         backup_interval: BackupInterval,
+        max_snapshot_size: MaxSnapshotSize,
+        tolerate_snapshot_errors: bool,
+        background_snapshot_writes: bool,
+        background_compaction: bool,
+        on_snapshot: Option<TdPyCallable>,
+        pickle_protocol: Option<i32>,
     ) -> ClockStream<S>;
 }
 
@@ -1702,12 +2572,19 @@ impl<S> RecoveryWriteOp<S> for Stream<S, Snapshot>
 where
     S: Scope<Timestamp = u64>,
 {
+    #[allow(clippy::too_many_arguments)]
     fn write_recovery(
         &self,
         resume_from: ResumeFrom,
         bundle: RecoveryBundle,
         epoch_interval: EpochInterval,
         backup_interval: BackupInterval,
+        max_snapshot_size: MaxSnapshotSize,
+        tolerate_snapshot_errors: bool,
+        background_snapshot_writes: bool,
+        background_compaction: bool,
+        on_snapshot: Option<TdPyCallable>,
+        pickle_protocol: Option<i32>,
     ) -> ClockStream<S> {
         let scope = self.scope();
         let local_parts = bundle.local_parts();
@@ -1732,16 +2609,23 @@ where
         let mut new_front_part = bundle.new_builder();
         let mut new_commit_part = bundle.new_builder();
 
-        let write_snap_clock = self.ser_snap().partd_write(
-            String::from("recovery_snap_writer"),
-            local_parts.clone(),
-            BuildHasherDefault::<SeaHasher>::default(),
-            move |part_key| {
-                let part = new_snap_part(part_key);
-                let writer = part.borrow().snap_writer();
-                writer
-            },
-        );
+        let write_snap_clock = self
+            .ser_snap(
+                max_snapshot_size,
+                tolerate_snapshot_errors,
+                on_snapshot,
+                pickle_protocol,
+            )
+            .partd_write(
+                String::from("recovery_snap_writer"),
+                local_parts.clone(),
+                BuildHasherDefault::<SeaHasher>::default(),
+                move |part_key| {
+                    let part = new_snap_part(part_key);
+                    let writer = part.borrow().snap_writer(background_snapshot_writes);
+                    writer
+                },
+            );
 
         write_ex_clock
             .concat(&write_snap_clock)
@@ -1765,7 +2649,7 @@ where
                 local_parts,
                 move |part_key| {
                     let part = new_commit_part(part_key);
-                    let committer = part.borrow().committer(*part_key);
+                    let committer = part.borrow().committer(*part_key, background_compaction);
                     committer
                 },
                 epoch_interval.epochs_per(backup_interval.0),
@@ -1934,7 +2818,11 @@ where
 
 pub(crate) fn register(py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(init_db_dir, m)?)?;
+    m.add_function(wrap_pyfunction!(assign_worker_index, m)?)?;
+    m.add_function(wrap_pyfunction!(read_snapshots, m)?)?;
     m.add_class::<RecoveryConfig>()?;
+    m.add_class::<RecoverySnapshot>()?;
+    m.add_class::<SnapshotInfo>()?;
     m.add(
         "InconsistentPartitionsError",
         py.get_type_bound::<InconsistentPartitionsError>(),
@@ -21,6 +21,7 @@ use tokio::runtime::Runtime;
 use crate::dataflow::Dataflow;
 use crate::errors::prepend_tname;
 use crate::errors::tracked_err;
+use crate::errors::BytewaxInternalError;
 use crate::errors::PythonException;
 use crate::inputs::EpochInterval;
 use crate::metrics::initialize_metrics;
@@ -67,6 +68,32 @@ fn start_server_runtime(df: Dataflow) -> PyResult<Runtime> {
     Ok(rt)
 }
 
+/// Set when {py:obj}`drain` is called, checked by input operators on
+/// every worker so they can stop ingesting new items while letting
+/// stateful logic finish `on_eof` and take a final snapshot, rather
+/// than the abrupt shutdown a signal or `abort` triggers.
+///
+/// There's only ever one dataflow execution per process, so a plain
+/// process-global flag (rather than something threaded in from the
+/// caller, like `abort`) is enough.
+pub(crate) static DRAIN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Request a graceful drain of the currently running dataflow.
+///
+/// Input sources will stop reading new items as soon as possible, but
+/// already-ingested items still flow through the dataflow so stateful
+/// steps get to run `on_eof` and take a final recovery snapshot
+/// before the dataflow shuts down.
+///
+/// Safe to call from a different thread than the one executing
+/// {py:obj}`run_main` or {py:obj}`cluster_main`, e.g. from a signal
+/// handler.
+#[pyfunction]
+pub(crate) fn drain() {
+    tracing::info!("Drain requested");
+    DRAIN_REQUESTED.store(true, Ordering::Relaxed);
+}
+
 /// Execute a dataflow in the current thread.
 ///
 /// Blocks until execution is complete.
@@ -161,18 +188,21 @@ pub(crate) fn run_main(
                 err.clone_ref(py)
             }
         } else if let Some(msg) = panic_err.downcast_ref::<String>() {
-            // Panics with String payload usually comes from timely here.
-            tracked_err::<PyRuntimeError>(msg)
+            // Panics with String payload usually come from an
+            // internal `assert!` here or from Timely; neither
+            // originates from a user callback, so surface this as a
+            // broken Bytewax invariant rather than a generic error.
+            tracked_err::<BytewaxInternalError>(msg)
         } else if let Some(msg) = panic_err.downcast_ref::<&str>() {
             // Panic with &str payload, usually from a direct call to `panic!`
-            // or `.expect`
-            tracked_err::<PyRuntimeError>(msg)
+            // or `.expect` inside Bytewax itself.
+            tracked_err::<BytewaxInternalError>(msg)
         } else {
             // Give up trying to understand the error, and show the user
             // a really helpful message.
             // We could show the debug representation of `panic_err`, but
             // it would just be `Any { .. }`
-            tracked_err::<PyRuntimeError>("unknown error")
+            tracked_err::<BytewaxInternalError>("unknown error")
         }
     })
 }
@@ -283,9 +313,10 @@ pub(crate) fn cluster_main(
                 // Panics with PyErr as payload should come from bytewax.
                 Python::with_gil(|py| err.clone_ref(py))
             } else {
-                // Give up trying to understand the error,
-                // and show the user what we have.
-                tracked_err::<PyRuntimeError>(&format!("{info}"))
+                // Not a re-raised user-code `PyErr`, so this is a
+                // broken Bytewax invariant (e.g. an internal
+                // `assert!`) rather than a generic error.
+                tracked_err::<BytewaxInternalError>(&format!("{info}"))
             };
             // Prepend the name of the thread to each line
             let msg = prepend_tname(msg.to_string());
@@ -391,5 +422,6 @@ pub(crate) fn register(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(run_main, m)?)?;
     m.add_function(wrap_pyfunction!(cluster_main, m)?)?;
     m.add_function(wrap_pyfunction!(cli_main, m)?)?;
+    m.add_function(wrap_pyfunction!(drain, m)?)?;
     Ok(())
 }
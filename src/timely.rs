@@ -89,6 +89,38 @@ where
     pub(crate) fn epochs(&self) -> impl Iterator<Item = T> + '_ {
         self.buffer.keys().cloned()
     }
+
+    /// Number of distinct epochs currently buffered awaiting the
+    /// input frontier to advance far enough to process them.
+    ///
+    /// Used to feed a `notificator_pending_epochs` gauge so a growing
+    /// backlog (this operator falling behind the frontier) is visible
+    /// from the outside.
+    pub(crate) fn pending_epoch_count(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Total number of items across all buffered epochs.
+    ///
+    /// Used to feed a `notificator_pending_items` gauge alongside
+    /// [`Self::pending_epoch_count`].
+    pub(crate) fn pending_item_count(&self) -> usize {
+        self.buffer.values().map(Vec::len).sum()
+    }
+
+    /// Put items back for an epoch so they're picked up again on a
+    /// later call to [`Self::remove`], e.g. when a consumer only
+    /// partially drained a batch and wants the rest re-delivered
+    /// instead of buffering it itself.
+    ///
+    /// A no-op if `items` is empty, so it's safe to call
+    /// unconditionally.
+    pub(crate) fn requeue(&mut self, epoch: T, mut items: Vec<D>) {
+        if items.is_empty() {
+            return;
+        }
+        self.buffer.entry(epoch).or_default().append(&mut items);
+    }
 }
 
 /// Extension trait for frontiers.
@@ -203,6 +235,17 @@ where
         self.queue.insert(epoch);
     }
 
+    /// Drop all retained capabilities, ending the stream as if EOF
+    /// had occurred.
+    ///
+    /// Call this after [`for_each`] to let a caller opt out of
+    /// processing any further epochs, e.g. in response to a callback
+    /// asking for a controlled early shutdown. Any epochs still
+    /// queued via [`notify_at`] are silently abandoned.
+    pub(crate) fn halt(&mut self) {
+        self.caps_state = None;
+    }
+
     /// Do some logic in epoch order eagerly.
     ///
     /// Call this on each operator activation with the current input
@@ -568,6 +611,117 @@ where
     }
 }
 
+/// Like [`PartitionFn`], but a single key can fan out to several
+/// partitions at once, e.g. duplicating a record across shards.
+pub(crate) trait FanoutPartitionFn<K> {
+    /// Determine the partition indices for this key.
+    ///
+    /// `known` is the full set of partitions seen so far, in sorted
+    /// order, in case an implementation needs to look a key up
+    /// directly rather than hash it.
+    ///
+    /// Each return value is independently modulo wrapped into the
+    /// total number of known partitions. Return a single-element
+    /// vec for the common case of one partition per key.
+    fn assign(&self, key: &K, known: &BTreeSet<K>) -> Vec<usize>;
+}
+
+pub(crate) trait PartitionFanoutOp<S, K, V>
+where
+    S: Scope,
+    S::Timestamp: TotalOrder,
+    K: Data,
+    V: Data,
+{
+    /// Like [`PartitionOp::partition`], but each incoming key-value
+    /// tuple can be duplicated out to multiple partitions.
+    ///
+    /// Also requires a stream of known partitions. The partition key
+    /// type is the same as the item key type so `pf` can look a key
+    /// up directly among the known partitions if it wants to.
+    fn partition_fanout(
+        &self,
+        name: String,
+        known: &Stream<S, K>,
+        pf: impl FanoutPartitionFn<K> + 'static,
+    ) -> Stream<S, (K, (K, V))>;
+}
+
+impl<S, K, V> PartitionFanoutOp<S, K, V> for Stream<S, (K, V)>
+where
+    S: Scope,
+    S::Timestamp: TotalOrder,
+    K: Data + Ord + Eq + Debug,
+    V: Data,
+{
+    fn partition_fanout(
+        &self,
+        name: String,
+        known: &Stream<S, K>,
+        pf: impl FanoutPartitionFn<K> + 'static,
+    ) -> Stream<S, (K, (K, V))> {
+        let mut op_builder = OperatorBuilder::new(name.clone(), self.scope());
+
+        let mut items_input = op_builder.new_input(self, Pipeline);
+        let mut known_input = op_builder.new_input(known, Pipeline);
+
+        let (mut partd_output, partd_items) = op_builder.new_output();
+
+        op_builder.build(move |init_caps| {
+            let known: BTreeSet<K> = BTreeSet::new();
+
+            let mut items_inbuf = InBuffer::new();
+            let mut known_inbuf = InBuffer::new();
+            let mut ncater = EagerNotificator::new(init_caps, known);
+
+            move |input_frontiers| {
+                tracing::debug_span!("operator", operator = name).in_scope(|| {
+                    items_input.buffer_notify(&mut items_inbuf, &mut ncater);
+                    known_input.buffer_notify(&mut known_inbuf, &mut ncater);
+
+                    ncater.for_each(
+                        input_frontiers,
+                        |caps, known| {
+                            let cap = &caps[0];
+                            let epoch = cap.time();
+
+                            if let Some(items) = items_inbuf.remove(epoch) {
+                                assert!(!known.is_empty(), "Known partitions in {name} is empty; did you forget to broadcast initial partitions in the 0th epoch?");
+
+                                let mut handle = partd_output.activate();
+                                let mut session = handle.session(cap);
+                                let len = known.len();
+                                for (key, value) in items {
+                                    for idx in pf.assign(&key, known) {
+                                        let wrapped_idx = idx % len;
+                                        tracing::trace!("Assigner gave value {idx} % {len}; wrapped to {wrapped_idx}");
+                                        let part = known
+                                            .iter()
+                                            .nth(wrapped_idx)
+                                            .expect("hash idx was not in len of known parts")
+                                            .clone();
+                                        session.give((part, (key.clone(), value.clone())));
+                                    }
+                                }
+                            }
+                        },
+                        |caps, known| {
+                            let cap = &caps[0];
+                            let epoch = cap.time();
+
+                            if let Some(parts) = known_inbuf.remove(epoch) {
+                                known.extend(parts);
+                            }
+                        },
+                    );
+                });
+            }
+        });
+
+        partd_items
+    }
+}
+
 /// Figure out a "primary" worker for each partition. Do this so we
 /// load balance worker use.
 ///
@@ -631,7 +785,19 @@ where
     ///
     /// Primary worker assignments are only emitted at the end of each
     /// epoch.
-    fn assign_primaries(&self, name: String) -> Stream<S, (P, WorkerIndex)>;
+    ///
+    /// An optional `overrides` function can be supplied to pin
+    /// specific partitions to specific workers (e.g. for
+    /// data-locality) instead of using the balanced assignment.
+    /// It's called with all currently known partitions and any
+    /// partition it maps to a worker takes precedence over the
+    /// balanced calculation; partitions it leaves unmapped still fall
+    /// back to [`calc_primaries`].
+    fn assign_primaries(
+        &self,
+        name: String,
+        overrides: Option<Box<dyn Fn(&[P]) -> BTreeMap<P, WorkerIndex>>>,
+    ) -> Stream<S, (P, WorkerIndex)>;
 }
 
 impl<S, P> AssignPrimariesOp<S, P> for Stream<S, (P, WorkerIndex)>
@@ -640,7 +806,11 @@ where
     S::Timestamp: TotalOrder,
     P: ExchangeData + Ord + Eq + Debug,
 {
-    fn assign_primaries(&self, name: String) -> Stream<S, (P, WorkerIndex)> {
+    fn assign_primaries(
+        &self,
+        name: String,
+        overrides: Option<Box<dyn Fn(&[P]) -> BTreeMap<P, WorkerIndex>>>,
+    ) -> Stream<S, (P, WorkerIndex)> {
         // Route all data to worker 0, this means that only worker 0
         // will assign primaries. We'll broadcast them at the end of
         // this operator.
@@ -680,9 +850,20 @@ where
                             let cap = &caps[0];
                             let epoch = cap.time();
 
-                            let new_primaries = calc_primaries(known);
+                            let mut new_primaries = calc_primaries(known);
                             if new_primaries.is_empty() {
-                                panic!("No partitions found on any worker; did you forget to init them?");
+                                panic!(
+                                    "{name}: no partitions found on any worker; did you forget \
+                                    to init them?"
+                                );
+                            }
+                            if let Some(overrides) = &overrides {
+                                let known_parts: Vec<P> = known.keys().cloned().collect();
+                                for (part, worker) in overrides(&known_parts) {
+                                    if known.contains_key(&part) {
+                                        new_primaries.insert(part, worker);
+                                    }
+                                }
                             }
 
                             let mut handle = routing_output.activate();
@@ -820,7 +1001,35 @@ pub(crate) trait Writer {
     type Item;
 
     /// Write a batch of items.
-    fn write_batch(&mut self, items: Vec<Self::Item>);
+    ///
+    /// Kept generic over the error type (rather than depending on
+    /// PyO3) so this module stays usable outside of the Python
+    /// bindings.
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Block until every batch handed to [`Self::write_batch`] so far
+    /// is durably written.
+    ///
+    /// Most writers write synchronously already, so the default no-op
+    /// is correct for them. A writer that hands batches off to a
+    /// background thread (to overlap writing one partition with
+    /// another) should override this to wait for that thread to catch
+    /// up.
+    fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+impl<T> Writer for Box<dyn Writer<Item = T>> {
+    type Item = T;
+
+    fn write_batch(&mut self, items: Vec<Self::Item>) -> Result<(), Box<dyn std::error::Error>> {
+        (**self).write_batch(items)
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        (**self).flush()
+    }
 }
 
 pub(crate) trait PartitionedWriteOp<S, K, V>
@@ -838,7 +1047,12 @@ where
     /// if it is assigned to be primary for that partition.
     ///
     /// Outputs downstream a clock so you can monitor what epoch's
-    /// items have been fully written.
+    /// items have been fully written. While any partition is
+    /// degraded (its last write or flush failed and its items are
+    /// being held for retry), the clock is held back at the last
+    /// epoch every partition wrote successfully, so a downstream
+    /// resume-epoch calculation never considers a retried-but-not-yet-
+    /// durable epoch safe.
     ///
     /// This can't be unified into the output system operators because
     /// they are either stateful (and we'd have a circular dependency
@@ -881,7 +1095,23 @@ where
             .f64_histogram("partd_write_duration_seconds")
             .with_description("partitioned state write duration in seconds")
             .init();
+        // Separate from `partd_write_duration_seconds` above so the
+        // recovery store's write latency can be isolated per-writer
+        // (e.g. snapshots vs frontier updates) rather than lumped
+        // together under `part_id` alone.
+        let store_histogram = meter
+            .f64_histogram("recovery_store_write_duration_seconds")
+            .with_description("recovery store write duration in seconds")
+            .init();
+        // Counts partitions that fail to write, e.g. because their
+        // backing store has gone read-only. We don't panic on these
+        // (see below), so this is how that's surfaced to operators.
+        let write_failed_counter = meter
+            .u64_counter("snapshot_write_failed")
+            .with_description("count of partition writes that failed and were held for retry")
+            .init();
         let worker_label = KeyValue::new("worker_id", this_worker.0.to_string());
+        let store_labels = vec![worker_label.clone(), KeyValue::new("writer", name.clone())];
         // Create a map of metric labels to use for each part_id
         let part_label_map: HashMap<P, Vec<KeyValue>> = local_parts
             .iter()
@@ -897,7 +1127,7 @@ where
             .collect();
 
         let all_parts = local_parts.into_broadcast(&self.scope(), S::Timestamp::minimum());
-        let primary_updates = all_parts.assign_primaries(format!("{name}.assign_primaries"));
+        let primary_updates = all_parts.assign_primaries(format!("{name}.assign_primaries"), None);
 
         let routed_self = self
             .partition(
@@ -917,11 +1147,28 @@ where
 
         op_builder.build(move |init_caps| {
             let parts: BTreeMap<P, W> = BTreeMap::new();
+            // Partitions whose last write or flush failed. Tracked
+            // purely to log/count state transitions once instead of on
+            // every failed batch, and to know which partitions still
+            // have items buffered for retry.
+            let degraded: BTreeSet<P> = BTreeSet::new();
 
             let mut routed_tmp = Vec::new();
             let mut items_inbuf: BTreeMap<S::Timestamp, BTreeMap<P, Vec<V>>> = BTreeMap::new();
             let mut primaries_inbuf = InBuffer::new();
-            let mut ncater = EagerNotificator::new(init_caps, parts);
+            // `ncater`'s own capabilities downgrade every activation
+            // regardless of write success, since it's shared,
+            // degraded-unaware bookkeeping used by other operators
+            // too. We instead track our own, separate capability for
+            // `clock_output` here and only ever downgrade it up to an
+            // epoch once every partition's write for that epoch (and
+            // all before it) has actually succeeded. Since a Timely
+            // output's frontier is the minimum over every capability
+            // still held for it, retaining this lagging capability
+            // while degraded is what actually keeps the resume point
+            // from advancing, regardless of `ncater`'s own caps.
+            let mut held_clock_cap = Some(init_caps[0].clone());
+            let mut ncater = EagerNotificator::new(init_caps, (parts, degraded));
 
             move |input_frontiers| {
                 tracing::debug_span!("operator", operator = op_name).in_scope(|| {
@@ -943,31 +1190,116 @@ where
 
                 ncater.for_each(
                     input_frontiers,
-                    |caps, parts| {
+                    |caps, (parts, degraded)| {
                         let cap = &caps[0];
                         let epoch = cap.time();
 
                         // Writing happens eagerly in each epoch. We
                         // still use a notificator at all because we
                         // need to ensure that writes happen in epoch
-                        // order.
-                        if let Some(part_to_items) = items_inbuf.remove(epoch) {
+                        // order. Coalesce every already-closed epoch
+                        // up to and including this one that arrived
+                        // in the same activation into a single
+                        // `write_batch` call per partition, rather
+                        // than one call per epoch.
+                        let ready_epochs: Vec<_> =
+                            items_inbuf.range(..=epoch.clone()).map(|(e, _)| e.clone()).collect();
+                        let mut merged: BTreeMap<P, Vec<V>> = BTreeMap::new();
+                        for ready_epoch in ready_epochs {
+                            if let Some(part_to_items) = items_inbuf.remove(&ready_epoch) {
+                                for (part_key, items) in part_to_items {
+                                    merged.entry(part_key).or_insert_with(Vec::new).extend(items);
+                                }
+                            }
+                        }
+
+                        if !merged.is_empty() {
                             let known_parts: Vec<_> = parts.keys().cloned().collect();
-                            for (part_key, items) in part_to_items {
+                            // Hand every partition its batch before
+                            // flushing any of them, so a writer that
+                            // offloads to a background thread (see
+                            // `BackgroundWriter` in `recovery.rs`) can
+                            // overlap one partition's write with
+                            // another's instead of them serializing.
+                            let mut touched = Vec::new();
+                            for (part_key, items) in merged {
                                 let part = parts
                                     .get_mut(&part_key)
                                     .unwrap_or_else(|| {
                                         panic!("Items routed to partition {part_key} but this worker only has {known_parts:?}");
                                     });
 
-                                let labels = part_label_map
-                                    .get(&part_key)
-                                    .expect("No metric labels found for part key {part_key}");
-                                with_timer!(histogram, labels, part.write_batch(items));
+                                let labels = part_label_map.get(&part_key).unwrap_or_else(|| {
+                                    panic!("No metric labels found for part key {part_key}")
+                                });
+                                // Keep a copy around in case the write
+                                // fails, so the items aren't lost and
+                                // can be retried alongside whatever
+                                // epoch closes next.
+                                let retry_items = items.clone();
+                                let now = std::time::Instant::now();
+                                let result = part.write_batch(items);
+                                let elapsed = now.elapsed().as_secs_f64();
+                                histogram.record(elapsed, labels);
+                                store_histogram.record(elapsed, &store_labels);
+                                match result {
+                                    Ok(()) => {
+                                        if degraded.remove(&part_key) {
+                                            tracing::info!(
+                                                "partition {part_key} recovered from a \
+                                                write failure; resuming normal writes"
+                                            );
+                                        }
+                                        touched.push(part_key);
+                                    }
+                                    Err(err) => {
+                                        write_failed_counter.add(1, labels);
+                                        if degraded.insert(part_key.clone()) {
+                                            tracing::error!(
+                                                "partition {part_key} failed to write at \
+                                                epoch {epoch:?}, entering degraded mode \
+                                                and retrying with later epochs: {err}"
+                                            );
+                                        }
+                                        items_inbuf
+                                            .entry(epoch.clone())
+                                            .or_insert_with(BTreeMap::new)
+                                            .entry(part_key)
+                                            .or_insert_with(Vec::new)
+                                            .extend(retry_items);
+                                    }
+                                }
+                            }
+                            for part_key in touched {
+                                let part = parts.get_mut(&part_key).unwrap_or_else(|| {
+                                    panic!("Items routed to partition {part_key} but this worker only has {known_parts:?}");
+                                });
+                                let labels = part_label_map.get(&part_key).unwrap_or_else(|| {
+                                    panic!("No metric labels found for part key {part_key}")
+                                });
+                                match part.flush() {
+                                    Ok(()) => {
+                                        if degraded.remove(&part_key) {
+                                            tracing::info!(
+                                                "partition {part_key} recovered from a \
+                                                write failure; resuming normal writes"
+                                            );
+                                        }
+                                    }
+                                    Err(err) => {
+                                        write_failed_counter.add(1, labels);
+                                        if degraded.insert(part_key.clone()) {
+                                            tracing::error!(
+                                                "partition {part_key} failed to flush at \
+                                                epoch {epoch:?}, entering degraded mode: {err}"
+                                            );
+                                        }
+                                    }
+                                }
                             }
                         }
                     },
-                    |caps, parts| {
+                    |caps, (parts, degraded)| {
                         let cap = &caps[0];
                         let epoch = cap.time();
 
@@ -980,14 +1312,35 @@ where
                                     parts.insert(part_key, part);
                                 } else {
                                     parts.remove(&part_key);
+                                    degraded.remove(&part_key);
                                 }
                             }
                         }
 
-                        // Emit our progress clock.
-                        clock_output.activate().session(cap).give(());
+                        // Only advance our progress clock up to this
+                        // epoch if every partition is caught up.
+                        // While any partition is degraded, the only
+                        // copy of its unwritten items is the
+                        // in-memory `items_inbuf` retry queue, so
+                        // reporting this epoch as safe to resume from
+                        // would let recovery skip past data that
+                        // never made it to disk.
+                        if degraded.is_empty() {
+                            if let Some(held_cap) = held_clock_cap.as_mut() {
+                                held_cap.downgrade(epoch);
+                                clock_output.activate().session(held_cap).give(());
+                            }
+                        }
                     },
                 );
+
+                // On EOF, release our held capability too so the
+                // clock stream actually closes rather than hanging
+                // downstream forever behind a partition that never
+                // recovered.
+                if input_frontiers.simplify().is_none() {
+                    held_clock_cap = None;
+                }
                 });
             }
         });
@@ -1133,7 +1486,7 @@ where
         // assigned a primary, so it'll also be the 0th epoch.
         let primary_updates = local_parts
             .into_broadcast(self, epoch)
-            .assign_primaries(format!("{name}.assign_primaries"));
+            .assign_primaries(format!("{name}.assign_primaries"), None);
 
         let op_name = format!("{name}.partd_load");
         let mut op_builder = OperatorBuilder::new(op_name.clone(), self.clone());
@@ -1285,7 +1638,7 @@ where
 
         let primary_updates = local_parts
             .into_broadcast(&self.scope(), S::Timestamp::minimum())
-            .assign_primaries(format!("{name}.assign_primaries"));
+            .assign_primaries(format!("{name}.assign_primaries"), None);
 
         let op_name = format!("{name}.partd_commit");
         let mut op_builder = OperatorBuilder::new(op_name.clone(), self.scope());
@@ -32,6 +32,7 @@ use crate::inputs::*;
 use crate::operators::*;
 use crate::outputs::*;
 use crate::pyo3_extensions::TdPyAny;
+use crate::pyo3_extensions::TdPyCallable;
 use crate::recovery::*;
 
 /// Bytewax worker.
@@ -116,7 +117,7 @@ where
 
     let resume_from = recovery
         .as_ref()
-        .map(|(bundle, _backup_interval)| -> PyResult<ResumeFrom> {
+        .map(|(bundle, _mirrors, _backup_interval, _max_snapshot_size, _tolerate_snapshot_errors, _background_snapshot_writes, _background_compaction, _on_snapshot, _pickle_protocol)| -> PyResult<ResumeFrom> {
             let resume_calc = Python::with_gil(|py| Rc::new(RefCell::new(ResumeCalc::new(py))));
             let resume_calc_d = resume_calc.clone();
             let probe = Python::with_gil(|py| {
@@ -145,6 +146,7 @@ where
             resume_from,
             recovery,
             &worker.abort,
+            &crate::run::DRAIN_REQUESTED,
         )
         .reraise("error building production dataflow")
     })?;
@@ -258,8 +260,19 @@ fn build_production_dataflow<A>(
     flow: Dataflow,
     epoch_interval: EpochInterval,
     resume_from: ResumeFrom,
-    recovery: Option<(RecoveryBundle, BackupInterval)>,
+    recovery: Option<(
+        RecoveryBundle,
+        Vec<RecoveryBundle>,
+        BackupInterval,
+        MaxSnapshotSize,
+        bool,
+        bool,
+        bool,
+        Option<TdPyCallable>,
+        Option<i32>,
+    )>,
     abort: &Arc<AtomicBool>,
+    drain: &'static AtomicBool,
 ) -> PyResult<ProbeHandle<u64>>
 where
     A: Allocate,
@@ -277,13 +290,19 @@ where
 
         let ResumeFrom(_ex, resume_epoch) = resume_from;
 
-        let loads = if let Some((bundle, _backup_interval)) = &recovery {
+        let loads = if let Some((bundle, _mirror_bundles, _backup_interval, _max_snapshot_size, _tolerate_snapshot_errors, _background_snapshot_writes, _background_compaction, _on_snapshot, _pickle_protocol)) =
+            &recovery
+        {
             scope.load_snaps(resume_epoch, bundle.clone_ref(py))
         } else {
             // Load nothing from a previous execution.
             empty(scope)
         };
 
+        // Built once and shared by every `stateful_batch` step below,
+        // rather than each one building its own copy.
+        let workers = workers_stream(scope);
+
         // This contains steps we still need to compile. Starts with
         // the top-level steps in the dataflow.
         let mut build_stack = flow.substeps(py)?;
@@ -309,12 +328,13 @@ where
                     }
                     "branch" => {
                         let predicate = step.get_arg(py, "predicate")?.extract(py)?;
+                        let ordered = step.get_arg(py, "ordered")?.extract(py)?;
 
                         let up = streams
                             .get_upstream(py, &step, "up")
                             .reraise("core operator `branch` missing port")?;
 
-                        let (trues, falses) = up.branch(step_id, predicate)?;
+                        let (trues, falses) = up.branch(step_id, predicate, ordered)?;
 
                         streams
                             .insert_downstream(py, &step, "trues", trues)
@@ -323,6 +343,36 @@ where
                             .insert_downstream(py, &step, "falses", falses)
                             .reraise("core operator `branch` missing port")?;
                     }
+                    "unbranch" => {
+                        let trues = streams
+                            .get_upstream(py, &step, "trues")
+                            .reraise("core operator `unbranch` missing port")?;
+                        let falses = streams
+                            .get_upstream(py, &step, "falses")
+                            .reraise("core operator `unbranch` missing port")?;
+
+                        let down = trues.unbranch(step_id, &falses)?;
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `unbranch` missing port")?;
+                    }
+                    "branch_batch" => {
+                        let predicate = step.get_arg(py, "predicate")?.extract(py)?;
+
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `branch_batch` missing port")?;
+
+                        let (trues, falses) = up.branch_batch(step_id, predicate)?;
+
+                        streams
+                            .insert_downstream(py, &step, "trues", trues)
+                            .reraise("core operator `branch_batch` missing port")?;
+                        streams
+                            .insert_downstream(py, &step, "falses", falses)
+                            .reraise("core operator `branch_batch` missing port")?;
+                    }
                     "flat_map_batch" => {
                         let mapper = step.get_arg(py, "mapper")?.extract(py)?;
 
@@ -336,6 +386,70 @@ where
                             .insert_downstream(py, &step, "down", down)
                             .reraise("core operator `flat_map_batch` missing port")?;
                     }
+                    "rekey" => {
+                        let key_fn = step.get_arg(py, "key_fn")?.extract(py)?;
+
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `rekey` missing port")?;
+
+                        let down = up.extract_key(step_id.clone()).rekey(step_id, key_fn).wrap_key();
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `rekey` missing port")?;
+                    }
+                    "key_cardinality" => {
+                        let approx = step.get_arg(py, "approx")?.extract(py)?;
+
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `key_cardinality` missing port")?;
+
+                        let down = up.extract_key(step_id.clone()).key_cardinality(step_id, approx);
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `key_cardinality` missing port")?;
+                    }
+                    "profile" => {
+                        let sample_rate = step.get_arg(py, "sample_rate")?.extract(py)?;
+
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `profile` missing port")?;
+
+                        let down = up.profile(step_id, sample_rate);
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `profile` missing port")?;
+                    }
+                    "timestamp" => {
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `timestamp` missing port")?;
+
+                        let down = up.timestamp(step_id);
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `timestamp` missing port")?;
+                    }
+                    "sample_stream" => {
+                        let fraction = step.get_arg(py, "fraction")?.extract(py)?;
+                        let seed = step.get_arg(py, "seed")?.extract(py)?;
+
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `sample_stream` missing port")?;
+
+                        let down = up.sample_stream(step_id, fraction, seed);
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `sample_stream` missing port")?;
+                    }
                     "input" => {
                         let source = step.get_arg(py, "source")?.extract::<Source>(py)?;
 
@@ -348,6 +462,7 @@ where
                                     epoch_interval,
                                     &probe,
                                     abort,
+                                    drain,
                                     resume_epoch,
                                     &loads,
                                 )
@@ -368,6 +483,7 @@ where
                                     epoch_interval,
                                     &probe,
                                     abort,
+                                    drain,
                                     resume_epoch,
                                 )
                                 .reraise("error building DynamicSource")?;
@@ -384,12 +500,13 @@ where
                     }
                     "inspect_debug" => {
                         let inspector = step.get_arg(py, "inspector")?.extract(py)?;
+                        let heartbeat = step.get_arg(py, "heartbeat")?.extract(py)?;
 
                         let up = streams
                             .get_upstream(py, &step, "up")
                             .reraise("core operator `inspect_debug` missing port")?;
 
-                        let (down, clock) = up.inspect_debug(py, step_id, inspector)?;
+                        let (down, clock) = up.inspect_debug(py, step_id, inspector, heartbeat)?;
 
                         outputs.push(clock);
 
@@ -408,6 +525,33 @@ where
                             .insert_downstream(py, &step, "down", down)
                             .reraise("core operator `merge` missing port")?;
                     }
+                    "merge_isolated" => {
+                        let mapper = step.get_arg(py, "mapper")?.extract(py)?;
+
+                        let ups = streams
+                            .get_upmultistream(py, &step, "ups")
+                            .reraise("core operator `merge_isolated` missing port")?;
+
+                        let (down, errors) = scope.merge_isolated(py, step_id, mapper, ups)?;
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `merge_isolated` missing port")?;
+                        streams
+                            .insert_downstream(py, &step, "errors", errors)
+                            .reraise("core operator `merge_isolated` missing port")?;
+                    }
+                    "merge_tagged" => {
+                        let ups = streams
+                            .get_upmultistream(py, &step, "ups")
+                            .reraise("core operator `merge_tagged` missing port")?;
+
+                        let down = scope.merge_tagged(py, step_id, ups)?;
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `merge_tagged` missing port")?;
+                    }
                     "output" => {
                         let sink = step.get_arg(py, "sink")?.extract::<Sink>(py)?;
 
@@ -416,18 +560,29 @@ where
                             .reraise("core operator `output` missing port")?;
 
                         if let Ok(sink) = sink.extract::<FixedPartitionedSink>(py) {
-                            let (clock, snap) = up
+                            let (clock, snap, confirmations) = up
                                 .partitioned_output(py, step_id, sink, &loads)
                                 .reraise("error building FixedPartitionedSink")?;
 
                             outputs.push(clock.clone());
                             snaps.push(snap);
+
+                            streams
+                                .insert_downstream(py, &step, "down", confirmations)
+                                .reraise("core operator `output` missing port")?;
                         } else if let Ok(sink) = sink.extract::<DynamicSink>(py) {
-                            let clock = up
-                                .dynamic_output(py, step_id, sink)
+                            let (clock, snap) = up
+                                .dynamic_output(py, step_id, sink, &loads)
                                 .reraise("error building DynamicSink")?;
 
                             outputs.push(clock.clone());
+                            snaps.push(snap);
+
+                            // `DynamicSink` has no confirmation
+                            // contract; always give an empty stream.
+                            streams
+                                .insert_downstream(py, &step, "down", empty(scope))
+                                .reraise("core operator `output` missing port")?;
                         } else {
                             let msg = "unknown sink type";
                             return Err(tracked_err::<PyTypeError>(msg));
@@ -444,20 +599,73 @@ where
                             .insert_downstream(py, &step, "down", down)
                             .reraise("core operator `redistribute` missing port")?;
                     }
+                    "redistribute_to" => {
+                        let workers = step.get_arg(py, "workers")?.extract(py)?;
+
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `redistribute_to` missing port")?;
+
+                        let down = up.redistribute_to(step_id, workers)?;
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `redistribute_to` missing port")?;
+                    }
+                    "broadcast" => {
+                        let up = streams
+                            .get_upstream(py, &step, "up")
+                            .reraise("core operator `broadcast` missing port")?;
+
+                        let down = up.broadcast(step_id);
+
+                        streams
+                            .insert_downstream(py, &step, "down", down)
+                            .reraise("core operator `broadcast` missing port")?;
+                    }
                     "stateful_batch" => {
                         let builder = step.get_arg(py, "builder")?.extract(py)?;
+                        let emit_discards = step.get_arg(py, "emit_discards")?.extract(py)?;
+                        let partition_fn = step.get_arg(py, "partition_fn")?.extract(py)?;
+                        let partition_seed = step.get_arg(py, "partition_seed")?.extract(py)?;
+                        let notify_coalesce_interval = step
+                            .get_arg(py, "notify_coalesce_interval")?
+                            .extract(py)?;
+                        let snapshot_interval = step.get_arg(py, "snapshot_interval")?.extract(py)?;
+                        let resume_lazily = step.get_arg(py, "resume_lazily")?.extract(py)?;
 
                         let up = streams
                             .get_upstream(py, &step, "up")
                             .reraise("core operator `stateful_batch` missing port")?;
-
-                        let (down, snap) = up.stateful_batch(py, step_id, builder, resume_epoch, &loads)?;
+                        let ctrl_ups = streams
+                            .get_upmultistream(py, &step, "ctrl")
+                            .reraise("core operator `stateful_batch` missing port")?;
+                        let ctrl = scope.merge(py, StepId(format!("{step_id}.ctrl")), ctrl_ups)?;
+
+                        let (down, discards, snap) = up.stateful_batch(
+                            py,
+                            step_id,
+                            builder,
+                            resume_epoch,
+                            &loads,
+                            &workers,
+                            &ctrl,
+                            emit_discards,
+                            partition_fn,
+                            partition_seed,
+                            notify_coalesce_interval,
+                            snapshot_interval,
+                            resume_lazily,
+                        )?;
 
                         snaps.push(snap);
 
                         streams
                             .insert_downstream(py, &step, "down", down)
                             .reraise("core operator `stateful_batch` missing port")?;
+                        streams
+                            .insert_downstream(py, &step, "discards", discards)
+                            .reraise("core operator `stateful_batch` missing port")?;
                     }
                     name => {
                         let msg = format!("Unknown core operator {name:?}");
@@ -483,11 +691,55 @@ where
         }
 
         // Attach the probe to the relevant final output.
-        if let Some((bundle, backup_interval)) = recovery {
-            scope
-                .concatenate(snaps)
-                .write_recovery(resume_from, bundle, epoch_interval, backup_interval)
+        if let Some((
+            bundle,
+            mirror_bundles,
+            backup_interval,
+            max_snapshot_size,
+            tolerate_snapshot_errors,
+            background_snapshot_writes,
+            background_compaction,
+            on_snapshot,
+            pickle_protocol,
+        )) = recovery
+        {
+            let snaps = scope.concatenate(snaps);
+            snaps
+                .write_recovery(
+                    resume_from,
+                    bundle,
+                    epoch_interval,
+                    backup_interval,
+                    max_snapshot_size,
+                    tolerate_snapshot_errors,
+                    background_snapshot_writes,
+                    background_compaction,
+                    on_snapshot.clone(),
+                    pickle_protocol,
+                )
                 .probe_with(&mut probe);
+            // Mirror every snapshot write to each additional store too,
+            // but leave each mirror's clock unprobed: only the
+            // primary's progress gates the probe. Mirrors always use
+            // background writes regardless of
+            // `background_snapshot_writes`; `partd_write` still flushes
+            // each epoch's mirror writes on this worker thread, so a
+            // slow mirror stalls the worker just as a slow primary
+            // store does.
+            for mirror_bundle in mirror_bundles {
+                snaps.write_recovery(
+                    resume_from,
+                    mirror_bundle,
+                    epoch_interval,
+                    backup_interval,
+                    max_snapshot_size,
+                    tolerate_snapshot_errors,
+                    true,
+                    background_compaction,
+                    on_snapshot.clone(),
+                    pickle_protocol,
+                );
+            }
         } else {
             scope.concatenate(outputs).probe_with(&mut probe);
         }